@@ -1,31 +1,62 @@
-use std::default;
+use std::fmt;
 
-use crate::piece::PieceType;
+use crate::{piece::PieceType, square::Square};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Action {
-    pub to: i32,
-    pub from: i32,
+    pub to: Square,
+    pub from: Square,
     pub action_type: ActionType,
 }
 
 impl Action {
-    pub fn new(from: i32, to: i32, action_type: Option<ActionType>) -> Action {
+    pub fn new(from: Square, to: Square, action_type: Option<ActionType>) -> Action {
         Action {
             from,
             to,
-            action_type: match action_type {
-                Some(a_type) => a_type,
-                None => ActionType::default(),
-            },
+            action_type: action_type.unwrap_or_default(),
         }
     }
+
+    // Long algebraic notation as used by UCI, e.g. e2e4 or e7e8q
+    pub fn to_uci(&self) -> String {
+        let promotion = match self.action_type {
+            ActionType::Promote(piece_type) => piece_type.get_char().to_string(),
+            _ => String::new(),
+        };
+
+        format!("{}{}{}", self.from, self.to, promotion)
+    }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ActionType {
     #[default]
     Normal,
     Castle,
+    EnPassant,
     Promote(PieceType),
 }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoveParseError {
+    // The text isn't shaped like a move at all
+    InvalidFormat(String),
+    // The text is a move, but not one that can be played in this position
+    IllegalMove(String),
+    // More than one legal move matches the text
+    AmbiguousMove(String),
+}
+
+impl fmt::Display for MoveParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveParseError::InvalidFormat(text) => write!(f, "invalid move format {}", text),
+            MoveParseError::IllegalMove(text) => write!(f, "illegal move {}", text),
+            MoveParseError::AmbiguousMove(text) => write!(f, "ambiguous move {}", text),
+        }
+    }
+}
+
+impl std::error::Error for MoveParseError {}
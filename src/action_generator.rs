@@ -1,217 +1,340 @@
 use crate::{
     action::Action,
     board::{Board, PieceIndices},
-    directions::{allowed_directions, EAST, NORTH, SOUTH, WEST},
-    piece::PieceType,
-    precomputed_data::PrecomputedData,
+    directions::{pawn_directions, sliding_directions},
+    moves::{Move, MoveList},
+    piece::{Color, PieceType},
+    precomputed_data::PRECOMPUTED,
+    square::{Square, E1, E8},
+    square_set::SquareSet,
 };
 
 #[derive(Default)]
 pub struct ActionGenerator {
-    precomputed: PrecomputedData,
     current_indices: PieceIndices,
     opposition_indicies: PieceIndices,
+    en_passant: Option<Square>,
+    stage: GenerationStage,
+}
+
+// Which moves a call generates. The search asks for one stage at a time so a
+// cutoff on an early move saves generating the rest.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GenerationStage {
+    // Every pseudo-legal move at once
+    #[default]
+    All,
+    // Captures and promotions
+    Captures,
+    // Everything that is neither a capture nor a promotion
+    Quiets,
+    // Moves that may answer a check: king moves, and captures of or blocks
+    // against a lone checker
+    Evasions,
 }
 
 impl ActionGenerator {
-    pub fn generate_valid_actions(&mut self, board: Board, for_white: bool) -> Vec<Action> {
-        let (current_indices, opposition_indicies): (PieceIndices, PieceIndices) = match for_white {
-            true => (board.white_pieces, board.black_pieces),
-            _ => (board.black_pieces, board.white_pieces),
-        };
+    pub fn generate_valid_actions(&mut self, board: Board, color: Color) -> Vec<Action> {
+        self.generate_moves(&board, color)
+            .iter()
+            .map(|&mv| Action::from(mv))
+            .collect()
+    }
 
-        self.opposition_indicies = opposition_indicies;
-        self.current_indices = current_indices;
+    // Pseudo-legal actions for the side to move, minus any that leave its own
+    // king in check
+    pub fn generate_legal_actions(&mut self, board: &Board) -> Vec<Action> {
+        self.generate_legal_moves(board)
+            .iter()
+            .map(|&mv| Action::from(mv))
+            .collect()
+    }
 
-        let mut actions = Vec::new();
-        for pos in self.current_indices.iter() {
-            let piece = board.array[pos.to_owned() as usize];
+    pub fn generate_moves(&mut self, board: &Board, color: Color) -> MoveList {
+        self.generate_stage(board, color, GenerationStage::All)
+    }
 
-            let new_actions = match piece.piece_type {
-                PieceType::Pawn => {
-                    self.generate_pawn_moves(pos.to_owned(), piece.is_white, piece.has_moved)
-                }
-                PieceType::Knight => self.generate_knight_moves(pos.to_owned()),
-                _ => self.generate_sliding_moves(pos.to_owned(), piece.piece_type, piece.is_white),
+    pub fn generate_stage(
+        &mut self,
+        board: &Board,
+        color: Color,
+        stage: GenerationStage,
+    ) -> MoveList {
+        self.stage = stage;
+        self.current_indices.clone_from(board.pieces(color));
+        self.opposition_indicies
+            .clone_from(board.pieces(color.opposite()));
+        self.en_passant = board.en_passant;
+
+        let mut moves = MoveList::new();
+        for &pos in self.current_indices.iter() {
+            let Some(piece) = board.piece_at(pos) else {
+                continue;
             };
 
-            actions.extend(new_actions);
-        }
-        actions
-    }
-
-    fn king_in_check(&self, board: &Board, check_for_white: bool) -> bool {
-        let king_index = board
-            .array
-            .iter()
-            .position(|&p| p.piece_type.eq(&PieceType::King) && p.is_white == check_for_white)
-            .unwrap() as i32;
-
-        // Opposition bishop checking
-        for direction in allowed_directions(PieceType::Bishop, true) {
-            let limit = self
-                .precomputed
-                .get_edge_distance(king_index, direction.name);
-
-            for moves in 1..=limit {
-                let target = king_index + direction.offset * moves;
-                let target_piece = board.array[target as usize];
-                let is_opposition = target_piece.is_white != check_for_white;
-                let is_queen_or_king = target_piece.piece_type.eq(&PieceType::Bishop)
-                    || target_piece.piece_type.eq(&PieceType::King);
-
-                if !is_opposition {
-                    break;
+            match piece.piece_type {
+                PieceType::Pawn => {
+                    self.generate_pawn_moves(&mut moves, pos, piece.color, piece.has_moved)
                 }
-
-                if (target_piece.piece_type.eq(&PieceType::Bishop) || is_queen_or_king)
-                    && is_opposition
-                {
-                    return true;
+                PieceType::Knight => {
+                    self.generate_step_moves(&mut moves, pos, PRECOMPUTED.knight_targets(pos))
                 }
+                PieceType::King => {
+                    self.generate_step_moves(&mut moves, pos, PRECOMPUTED.king_targets(pos));
+                    if stage != GenerationStage::Captures {
+                        self.generate_castling_moves(&mut moves, board, pos);
+                    }
+                }
+                _ => self.generate_sliding_moves(&mut moves, pos, piece.piece_type),
             }
         }
 
-        // Opposition rook checking
-        for direction in allowed_directions(PieceType::Rook, true) {
-            let limit = self
-                .precomputed
-                .get_edge_distance(king_index, direction.name);
-
-            for moves in 1..=limit {
-                let target = king_index + direction.offset * moves;
-                let target_piece = board.array[target as usize];
-                let is_opposition = target_piece.is_white != check_for_white;
-                let is_queen_or_king = target_piece.piece_type.eq(&PieceType::Bishop)
-                    || target_piece.piece_type.eq(&PieceType::King);
-
-                if !is_opposition {
-                    return false;
-                }
-
-                if (target_piece.piece_type.eq(&PieceType::Rook) || is_queen_or_king)
-                    && is_opposition
-                {
-                    return true;
-                }
-            }
+        if stage == GenerationStage::Evasions {
+            self.retain_evasions(&mut moves, board, color);
         }
+        moves
+    }
+
+    // With two checkers only the king can move. A lone checker can also be
+    // captured, or blocked when it is a slider.
+    fn retain_evasions(&self, moves: &mut MoveList, board: &Board, color: Color) {
+        let Some(king) = board.king_square(color) else {
+            return;
+        };
+        let checkers = board.checkers();
+        let targets = match (checkers.first(), checkers.len()) {
+            (Some(checker), 1) => PRECOMPUTED.between(king, checker) | checkers,
+            _ => SquareSet::EMPTY,
+        };
 
-        false
+        moves.retain(|&mv| {
+            // An en passant capture removes a pawn that isn't on its target square
+            let captured = match mv.flags() == Move::EN_PASSANT {
+                true => Square::new(mv.to().file(), mv.from().rank()),
+                _ => Some(mv.to()),
+            };
+            mv.from() == king || captured.is_some_and(|captured| targets.contains(captured))
+        });
     }
 
-    fn generate_pawn_moves(&self, pos: i32, is_white: bool, has_moved: bool) -> Vec<Action> {
-        let mut actions = Vec::new();
+    pub fn generate_legal_moves(&mut self, board: &Board) -> MoveList {
+        let color = board.side_to_move;
+
+        let mut moves = self.generate_moves(board, color);
+        let king = board.king_square(color);
+        let pinned = board.pinned(color);
+        let in_check = !board.checkers().is_empty();
+
+        moves.retain(|&mv| {
+            // Out of check, only the king, en passant captures and pinned
+            // pieces can expose the king, and a pinned piece is safe moving
+            // along its pin
+            if let Some(king) = king
+                .filter(|&king| !in_check && king != mv.from() && mv.flags() != Move::EN_PASSANT)
+            {
+                return !pinned.contains(mv.from())
+                    || PRECOMPUTED.line(king, mv.from()).contains(mv.to());
+            }
 
-        let directions = allowed_directions(PieceType::Pawn, is_white);
+            let mut board_after_move = board.clone();
+            board_after_move.make_move(mv);
+            !board_after_move.in_check(color)
+        });
+        moves
+    }
 
-        for direction in directions {
-            let target = pos + direction.offset;
-            if direction.limit == 2 {
-                if !self.friendly_obstruction(&target) && !self.enemy_obstruction(&target) {
-                    actions.push(Action::new(pos, target, None));
+    fn generate_pawn_moves(
+        &self,
+        moves: &mut MoveList,
+        pos: Square,
+        color: Color,
+        has_moved: bool,
+    ) {
+        let (forward, captures) = pawn_directions(color);
+        let is_empty =
+            |target: &Square| !self.friendly_obstruction(target) && !self.enemy_obstruction(target);
+
+        let (file_step, rank_step) = forward.step();
+        if let Some(target) = pos.offset(file_step, rank_step).filter(is_empty) {
+            self.push_pawn_move(moves, pos, target, false);
+
+            let double_push = target.offset(file_step, rank_step);
+            if let Some(double_push) =
+                double_push.filter(|double_push| is_empty(double_push) && !has_moved)
+            {
+                self.push(moves, Move::new(pos, double_push, Move::DOUBLE_PUSH));
+            }
+        }
 
-                    let double_push = target + direction.offset;
-                    if !self.friendly_obstruction(&double_push)
-                        && !self.enemy_obstruction(&double_push)
-                        && !has_moved
-                    {
-                        actions.push(Action::new(pos, double_push, None));
-                    }
-                }
+        for direction in captures {
+            let (file_step, rank_step) = direction.step();
+            let Some(target) = pos.offset(file_step, rank_step) else {
                 continue;
-            }
+            };
 
             if self.enemy_obstruction(&target) {
-                actions.push(Action::new(pos, target, None));
+                self.push_pawn_move(moves, pos, target, true);
+            } else if self.en_passant == Some(target) {
+                self.push(moves, Move::new(pos, target, Move::EN_PASSANT));
             }
         }
+    }
+
+    fn push_pawn_move(&self, moves: &mut MoveList, pos: Square, target: Square, is_capture: bool) {
+        let is_promotion = target.rank() == 0 || target.rank() == 7;
+        if !is_promotion {
+            self.push(
+                moves,
+                Move::new(pos, target, Self::capture_flag(is_capture)),
+            );
+            return;
+        }
 
-        actions
+        for piece_type in [
+            PieceType::Queen,
+            PieceType::Rook,
+            PieceType::Bishop,
+            PieceType::Knight,
+        ] {
+            self.push(moves, Move::promotion(pos, target, piece_type, is_capture));
+        }
     }
 
-    fn generate_knight_moves(&self, pos: i32) -> Vec<Action> {
-        allowed_directions(PieceType::Knight, true)
-            .into_iter()
-            .flat_map(|dir| {
-                if self.precomputed.get_edge_distance(pos, dir.name) < 2 {
-                    return Vec::new();
-                }
+    // The king may not castle out of, through or into check
+    fn generate_castling_moves(&self, moves: &mut MoveList, board: &Board, pos: Square) {
+        let Some(king) = board.piece_at(pos) else {
+            return;
+        };
+        let enemy = king.color.opposite();
+        let rights = board.castling_rights;
+        let (home, king_side, queen_side) = match king.color {
+            Color::White => (E1, rights.white_king_side, rights.white_queen_side),
+            Color::Black => (E8, rights.black_king_side, rights.black_queen_side),
+        };
 
-                let p_pos = pos + dir.offset * 2;
-                let dist = self.precomputed.moves_to_edge[p_pos as usize];
+        if pos != home || board.is_square_attacked(pos, enemy) {
+            return;
+        }
 
-                match dir.name {
-                    "NORTH" | "SOUTH" => {
-                        self.get_knight_targets(p_pos, (WEST, EAST), (dist.west, dist.east))
-                    }
+        // The king's home square is in the middle of the back rank, so these
+        // are always on the board
+        let along_rank = |files: i32| pos.offset(files, 0).expect("castling stays on the board");
+        let is_empty = |files: i32| {
+            let target = along_rank(files);
+            !self.friendly_obstruction(&target) && !self.enemy_obstruction(&target)
+        };
 
-                    "EAST" | "WEST" => {
-                        self.get_knight_targets(p_pos, (NORTH, SOUTH), (dist.north, dist.south))
-                    }
-                    _ => vec![],
-                }
-                .iter()
-                .filter(move |&t| !self.friendly_obstruction(t))
-                .map(move |t| Action::new(pos, t.to_owned(), None))
-                .collect()
-            })
-            .collect()
-    }
+        if king_side
+            && is_empty(1)
+            && is_empty(2)
+            && !board.is_square_attacked(along_rank(1), enemy)
+        {
+            self.push(moves, Move::new(pos, along_rank(2), Move::KING_CASTLE));
+        }
 
-    fn get_knight_targets(&self, pos: i32, offsets: (i32, i32), distances: (i32, i32)) -> Vec<i32> {
-        let mut targets: Vec<i32> = Vec::new();
-        targets.extend(distances.0.gt(&0).then_some(pos + offsets.0));
-        targets.extend(distances.1.gt(&0).then_some(pos + offsets.1));
-        targets.into_iter().collect()
+        if queen_side
+            && is_empty(-1)
+            && is_empty(-2)
+            && is_empty(-3)
+            && !board.is_square_attacked(along_rank(-1), enemy)
+        {
+            self.push(moves, Move::new(pos, along_rank(-2), Move::QUEEN_CASTLE));
+        }
     }
 
-    fn generate_sliding_moves(
-        &self,
-        pos: i32,
-        piece_type: PieceType,
-        is_white: bool,
-    ) -> Vec<Action> {
-        let mut moves: Vec<Action> = Vec::new();
+    // Knights and kings: one step to any target not held by a friendly piece
+    fn generate_step_moves(&self, moves: &mut MoveList, pos: Square, targets: SquareSet) {
+        for target in targets {
+            if !self.friendly_obstruction(&target) {
+                let is_capture = self.enemy_obstruction(&target);
+                self.push(
+                    moves,
+                    Move::new(pos, target, Self::capture_flag(is_capture)),
+                );
+            }
+        }
+    }
 
-        for direction in allowed_directions(piece_type, is_white) {
-            let dist_from_edge = self.precomputed.get_edge_distance(pos, direction.name);
+    fn generate_sliding_moves(&self, moves: &mut MoveList, pos: Square, piece_type: PieceType) {
+        for &direction in sliding_directions(piece_type) {
+            let (file_step, rank_step) = direction.step();
 
-            for n in 1..=dist_from_edge.to_owned() {
-                if n > direction.limit {
+            for n in 1..=PRECOMPUTED.edge_distance(pos, direction) as i32 {
+                let Some(target_pos) = pos.offset(file_step * n, rank_step * n) else {
                     break;
-                }
-
-                let target_pos: i32 = pos + direction.offset * n;
+                };
 
                 if self.friendly_obstruction(&target_pos) {
                     break;
                 }
 
-                moves.push(Action::new(pos, target_pos, None));
+                let is_capture = self.enemy_obstruction(&target_pos);
+                self.push(
+                    moves,
+                    Move::new(pos, target_pos, Self::capture_flag(is_capture)),
+                );
 
-                if self.enemy_obstruction(&target_pos) {
+                if is_capture {
                     break;
                 }
             }
         }
+    }
 
-        moves
+    fn push(&self, moves: &mut MoveList, mv: Move) {
+        let is_tactical = mv.is_capture() || mv.is_promotion();
+        let wanted = match self.stage {
+            GenerationStage::Captures => is_tactical,
+            GenerationStage::Quiets => !is_tactical,
+            GenerationStage::All | GenerationStage::Evasions => true,
+        };
+
+        if wanted {
+            moves.push(mv);
+        }
     }
 
-    fn friendly_obstruction(&self, target: &i32) -> bool {
+    fn capture_flag(is_capture: bool) -> u16 {
+        match is_capture {
+            true => Move::CAPTURE,
+            _ => Move::QUIET,
+        }
+    }
+
+    fn friendly_obstruction(&self, target: &Square) -> bool {
         self.current_indices.contains(target)
     }
 
-    fn enemy_obstruction(&self, target: &i32) -> bool {
+    fn enemy_obstruction(&self, target: &Square) -> bool {
         self.opposition_indicies.contains(target)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::ActionGenerator;
-    use crate::{board::Board, precomputed_data::PrecomputedData};
+    use super::{ActionGenerator, GenerationStage};
+    use crate::square::{
+        Square, A1, A2, A3, A4, A8, B2, B3, C1, C2, D4, D5, D6, E2, E5, E7, G1, H1, H2, H3, H8,
+    };
+    use crate::{
+        action::ActionType,
+        board::Board,
+        moves::{Move, MoveList},
+        piece::{Color, PieceType},
+        precomputed_data::PRECOMPUTED,
+    };
+
+    fn knight_moves(gen: &ActionGenerator, pos: Square) -> MoveList {
+        let mut moves = MoveList::new();
+        gen.generate_step_moves(&mut moves, pos, PRECOMPUTED.knight_targets(pos));
+        moves
+    }
+
+    fn pawn_moves(gen: &ActionGenerator, pos: Square, color: Color, has_moved: bool) -> MoveList {
+        let mut moves = MoveList::new();
+        gen.generate_pawn_moves(&mut moves, pos, color, has_moved);
+        moves
+    }
 
     // KNIGHT MOVES
 
@@ -219,7 +342,7 @@ mod tests {
     fn generate_knight_moves_at_center_board() {
         let gen = ActionGenerator::default();
 
-        let moves = gen.generate_knight_moves(27);
+        let moves = knight_moves(&gen, D4);
 
         assert_eq!(moves.len(), 8);
     }
@@ -228,19 +351,19 @@ mod tests {
     fn generate_knight_moves_at_corner() {
         let gen = ActionGenerator::default();
 
-        let mut moves = gen.generate_knight_moves(0); // bottom left
-        moves.extend(gen.generate_knight_moves(7)); // bottom right
-        moves.extend(gen.generate_knight_moves(63)); // top right
-        moves.extend(gen.generate_knight_moves(56)); // top left
+        let moves = knight_moves(&gen, A1).len() // bottom left
+            + knight_moves(&gen, H1).len() // bottom right
+            + knight_moves(&gen, H8).len() // top right
+            + knight_moves(&gen, A8).len(); // top left
 
-        assert_eq!(moves.len(), 8);
+        assert_eq!(moves, 8);
     }
 
     #[test]
     fn generate_knight_moves_at_center_edge() {
         let gen = ActionGenerator::default();
 
-        let moves = gen.generate_knight_moves(24);
+        let moves = knight_moves(&gen, A4);
 
         assert_eq!(moves.len(), 4);
     }
@@ -249,7 +372,7 @@ mod tests {
     fn generate_knight_moves_at_corner_edge() {
         let gen = ActionGenerator::default();
 
-        let moves = gen.generate_knight_moves(15);
+        let moves = knight_moves(&gen, H2);
 
         assert_eq!(moves.len(), 3);
     }
@@ -257,11 +380,12 @@ mod tests {
     #[test]
     fn generate_knight_moves_with_blocks() {
         let gen = ActionGenerator {
-            current_indices: vec![10, 17],
+            current_indices: vec![C2, B3],
             opposition_indicies: vec![],
-            precomputed: PrecomputedData::default(),
+            en_passant: None,
+            stage: GenerationStage::All,
         };
-        let moves = gen.generate_knight_moves(0);
+        let moves = knight_moves(&gen, A1);
 
         assert_eq!(moves.len(), 0);
     }
@@ -269,12 +393,13 @@ mod tests {
     #[test]
     fn generate_knight_moves_with_with_captures() {
         let gen = ActionGenerator {
-            opposition_indicies: vec![10, 17],
+            opposition_indicies: vec![C2, B3],
             current_indices: vec![],
-            precomputed: PrecomputedData::default(),
+            en_passant: None,
+            stage: GenerationStage::All,
         };
 
-        let moves = gen.generate_knight_moves(0);
+        let moves = knight_moves(&gen, A1);
 
         assert_eq!(moves.len(), 2);
     }
@@ -284,7 +409,7 @@ mod tests {
     #[test]
     fn generate_pawn_moves_white_when_not_moved_and_no_captures() {
         let gen = ActionGenerator::default();
-        let moves = gen.generate_pawn_moves(8, true, false);
+        let moves = pawn_moves(&gen, A2, Color::White, false);
 
         assert_eq!(moves.len(), 2);
     }
@@ -292,7 +417,7 @@ mod tests {
     #[test]
     fn generate_pawn_moves_white_when_has_moved_and_no_caps() {
         let gen = ActionGenerator::default();
-        let moves = gen.generate_pawn_moves(8, true, true);
+        let moves = pawn_moves(&gen, A2, Color::White, true);
 
         assert_eq!(moves.len(), 1);
     }
@@ -300,11 +425,12 @@ mod tests {
     #[test]
     fn generate_pawn_moves_white_when_not_moved_and_captures() {
         let gen = ActionGenerator {
-            opposition_indicies: vec![10, 17],
+            opposition_indicies: vec![C2, B3],
             current_indices: vec![],
-            precomputed: PrecomputedData::default(),
+            en_passant: None,
+            stage: GenerationStage::All,
         };
-        let moves = gen.generate_pawn_moves(8, true, false);
+        let moves = pawn_moves(&gen, A2, Color::White, false);
 
         assert_eq!(moves.len(), 3);
     }
@@ -312,11 +438,12 @@ mod tests {
     #[test]
     fn generate_pawn_moves_white_when_not_moved_and_captures_and_blocks() {
         let gen = ActionGenerator {
-            opposition_indicies: vec![10, 17],
-            current_indices: vec![16],
-            precomputed: PrecomputedData::default(),
+            opposition_indicies: vec![C2, B3],
+            current_indices: vec![A3],
+            en_passant: None,
+            stage: GenerationStage::All,
         };
-        let moves = gen.generate_pawn_moves(8, true, false);
+        let moves = pawn_moves(&gen, A2, Color::White, false);
 
         assert_eq!(moves.len(), 1);
     }
@@ -326,7 +453,7 @@ mod tests {
     #[test]
     fn generate_pawn_moves_black_when_not_moved_and_no_captures() {
         let gen = ActionGenerator::default();
-        let moves = gen.generate_pawn_moves(43, false, false);
+        let moves = pawn_moves(&gen, D6, Color::Black, false);
 
         assert_eq!(moves.len(), 2);
     }
@@ -334,7 +461,7 @@ mod tests {
     #[test]
     fn generate_pawn_moves_black_when_has_moved_and_no_caps() {
         let gen = ActionGenerator::default();
-        let moves = gen.generate_pawn_moves(43, false, true);
+        let moves = pawn_moves(&gen, D6, Color::Black, true);
 
         assert_eq!(moves.len(), 1);
     }
@@ -342,84 +469,108 @@ mod tests {
     #[test]
     fn generate_pawn_moves_black_when_not_moved_and_captures() {
         let gen = ActionGenerator {
-            opposition_indicies: vec![8],
+            opposition_indicies: vec![A2],
             current_indices: vec![],
-            precomputed: PrecomputedData::default(),
+            en_passant: None,
+            stage: GenerationStage::All,
         };
 
-        let moves = gen.generate_pawn_moves(17, false, false);
+        let moves = pawn_moves(&gen, B3, Color::Black, false);
         assert_eq!(moves.len(), 3);
     }
 
     #[test]
     fn generate_pawn_moves_black_when_not_moved_and_captures_and_blocks() {
         let gen = ActionGenerator {
-            opposition_indicies: vec![10, 8],
-            current_indices: vec![9],
-            precomputed: PrecomputedData::default(),
+            opposition_indicies: vec![C2, A2],
+            current_indices: vec![B2],
+            en_passant: None,
+            stage: GenerationStage::All,
         };
-        let moves = gen.generate_pawn_moves(17, false, false);
+        let moves = pawn_moves(&gen, B3, Color::Black, false);
 
         assert_eq!(moves.len(), 2);
     }
 
     #[test]
-    fn king_in_check_white_complex_no_check() {
+    fn generate_pawn_moves_does_not_wrap_captures_around_the_edge() {
         let gen = ActionGenerator {
-            opposition_indicies: vec![10, 8],
-            current_indices: vec![9],
-            precomputed: PrecomputedData::default(),
+            opposition_indicies: vec![H3],
+            current_indices: vec![],
+            en_passant: None,
+            stage: GenerationStage::All,
         };
-        let board = Board::new("b2r2b1/1B3B2/3R4/2RK2Rr/r3B3/1B1R1b2/8/3r4 w - - 0 1");
-        let actual = gen.king_in_check(&board, true);
-        assert!(!actual);
+        let moves = pawn_moves(&gen, A2, Color::White, true);
+
+        assert_eq!(moves.len(), 1);
     }
 
     #[test]
-    fn king_in_check_white_rook_s() {
-        let gen = ActionGenerator {
-            opposition_indicies: vec![10, 8],
-            current_indices: vec![9],
-            precomputed: PrecomputedData::default(),
-        };
-        let board = Board::new("8/8/8/8/8/8/1r2K3/8 w - - 0 1");
-        let actual = gen.king_in_check(&board, true);
-        assert!(actual);
+    fn generate_pawn_moves_promotes_on_last_rank() {
+        let gen = ActionGenerator::default();
+        let moves = pawn_moves(&gen, E7, Color::White, true);
+
+        assert_eq!(moves.len(), 4);
+        assert!(moves
+            .iter()
+            .any(|m| m.promotion_piece() == Some(PieceType::Knight)));
     }
 
     #[test]
-    fn king_in_check_white_rook_w() {
-        let gen = ActionGenerator {
-            opposition_indicies: vec![10, 8],
-            current_indices: vec![9],
-            precomputed: PrecomputedData::default(),
-        };
-        let board = Board::new("8/8/8/8/8/8/1r2K3/8 w - - 0 1");
-        let actual = gen.king_in_check(&board, true);
-        assert!(actual);
+    fn generate_legal_actions_excludes_moves_of_pinned_pieces() {
+        let mut gen = ActionGenerator::default();
+        let board = Board::new("4r1k1/8/8/8/8/8/4B3/4K3 w - - 0 1");
+
+        let actions = gen.generate_legal_actions(&board);
+
+        assert!(actions.iter().all(|action| action.from != E2));
+        assert_eq!(actions.len(), 4);
     }
 
     #[test]
-    fn king_in_check_white_bishop_nw() {
+    fn generate_pawn_moves_captures_en_passant() {
         let gen = ActionGenerator {
-            opposition_indicies: vec![10, 8],
-            current_indices: vec![9],
-            precomputed: PrecomputedData::default(),
+            opposition_indicies: vec![D5],
+            current_indices: vec![],
+            en_passant: Some(D6),
+            stage: GenerationStage::All,
         };
-        let board = Board::new("8/8/8/8/b7/8/2K5/8 w - - 0 1");
-        let actual = gen.king_in_check(&board, true);
-        assert!(actual);
+        let moves = pawn_moves(&gen, E5, Color::White, true);
+
+        assert_eq!(moves.len(), 2);
+        assert!(moves
+            .iter()
+            .any(|m| m.to() == D6 && m.flags() == Move::EN_PASSANT));
     }
 
     #[test]
-    fn king_in_check_white_bishop_ne() {
-        let gen = ActionGenerator {
-            opposition_indicies: vec![10, 8],
-            current_indices: vec![9],
-            precomputed: PrecomputedData::default(),
-        };
-        let board = Board::new("8/8/8/5b2/8/8/2K5/8 w - - 0 1");
-        let actual = gen.king_in_check(&board, true);
-        assert!(actual);
+    fn generate_castling_moves_when_path_is_clear() {
+        let mut gen = ActionGenerator::default();
+        let board = Board::new("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+
+        let castles: Vec<_> = gen
+            .generate_legal_actions(&board)
+            .into_iter()
+            .filter(|action| action.action_type == ActionType::Castle)
+            .map(|action| action.to)
+            .collect();
+
+        assert_eq!(castles, vec![G1, C1]);
+    }
+
+    #[test]
+    fn generate_castling_moves_not_through_check_or_without_rights() {
+        let mut gen = ActionGenerator::default();
+        let through_check = Board::new("r3k2r/8/8/8/8/8/3r1r2/R3K2R w KQkq - 0 1");
+        let in_check = Board::new("r3k2r/8/8/8/8/8/4r3/R3K2R w KQkq - 0 1");
+        let no_rights = Board::new("r3k2r/8/8/8/8/8/8/R3K2R w kq - 0 1");
+        let blocked = Board::new("r3k2r/8/8/8/8/8/8/RN2K1NR w KQkq - 0 1");
+
+        for board in [through_check, in_check, no_rights, blocked] {
+            assert!(gen
+                .generate_legal_actions(&board)
+                .iter()
+                .all(|action| action.action_type != ActionType::Castle));
+        }
     }
 }
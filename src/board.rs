@@ -1,77 +1,648 @@
+use std::{
+    error, fmt,
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
 use crate::{
-    action::Action,
-    piece::{Piece, PieceType},
+    action::{Action, ActionType, MoveParseError},
+    action_generator::ActionGenerator,
+    directions::Direction,
+    log,
+    moves::Move,
+    piece::{Color, Piece, PieceType},
+    precomputed_data::PRECOMPUTED,
+    square::{Square, A1, A8, E1, E8, H1, H8},
+    square_set::SquareSet,
 };
 
-pub type BoardArray = [Piece; 64];
-pub type PieceIndices = Vec<i32>;
+pub const DEFAULT_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+pub type BoardArray = [Option<Piece>; 64];
+pub type PieceIndices = Vec<Square>;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CastlingRights {
+    pub white_king_side: bool,
+    pub white_queen_side: bool,
+    pub black_king_side: bool,
+    pub black_queen_side: bool,
+}
+
+impl CastlingRights {
+    fn new(fen_castling: &str) -> Self {
+        CastlingRights {
+            white_king_side: fen_castling.contains('K'),
+            white_queen_side: fen_castling.contains('Q'),
+            black_king_side: fen_castling.contains('k'),
+            black_queen_side: fen_castling.contains('q'),
+        }
+    }
+
+    // A king or rook leaving its home square, or a rook being captured on it,
+    // loses the corresponding rights for good
+    fn update(&mut self, square: Square) {
+        match square {
+            A1 => self.white_queen_side = false,
+            E1 => {
+                self.white_king_side = false;
+                self.white_queen_side = false;
+            }
+            H1 => self.white_king_side = false,
+            A8 => self.black_queen_side = false,
+            E8 => {
+                self.black_king_side = false;
+                self.black_queen_side = false;
+            }
+            H8 => self.black_king_side = false,
+            _ => {}
+        }
+    }
+
+    fn to_fen(self) -> String {
+        let rights = [
+            (self.white_king_side, 'K'),
+            (self.white_queen_side, 'Q'),
+            (self.black_king_side, 'k'),
+            (self.black_queen_side, 'q'),
+        ];
+        let fen: String = rights
+            .iter()
+            .filter(|(allowed, _)| *allowed)
+            .map(|(_, char)| char)
+            .collect();
+
+        match fen.is_empty() {
+            true => "-".to_string(),
+            _ => fen,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FenError {
+    MissingPlacement,
+    // Not eight ranks of eight squares, or a letter that isn't a piece
+    InvalidPlacement(String),
+    InvalidSideToMove(String),
+    InvalidCastling(String),
+    InvalidEnPassant(String),
+    InvalidMoveCounter(String),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::MissingPlacement => write!(f, "missing piece placement"),
+            FenError::InvalidPlacement(text) => write!(f, "invalid piece placement {}", text),
+            FenError::InvalidSideToMove(text) => write!(f, "invalid side to move {}", text),
+            FenError::InvalidCastling(text) => write!(f, "invalid castling rights {}", text),
+            FenError::InvalidEnPassant(text) => write!(f, "invalid en passant square {}", text),
+            FenError::InvalidMoveCounter(text) => write!(f, "invalid move counter {}", text),
+        }
+    }
+}
+
+impl error::Error for FenError {}
+
+// The state a null move discards, handed back to unmake_null_move
+#[derive(Debug, Clone, Copy)]
+pub struct NullMoveUndo {
+    en_passant: Option<Square>,
+    halfmove_clock: u32,
+    checkers: SquareSet,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameStatus {
+    Ongoing,
+    // Holds the colour that delivered mate
+    Checkmate(Color),
+    Stalemate,
+    Draw(DrawReason),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawReason {
+    FiftyMoveRule,
+    ThreefoldRepetition,
+    InsufficientMaterial,
+}
 
 #[derive(Debug, Clone)]
 pub struct Board {
     pub array: BoardArray,
-    pub white_pieces: PieceIndices,
-    pub black_pieces: PieceIndices,
-    pub white_in_check: bool,
-    pub black_in_check: bool,
+    // Both of these are indexed by Color::index
+    pub pieces: [PieceIndices; 2],
+    pub checks: [bool; 2],
+    pub side_to_move: Color,
+    // Cached after every move, see update_checks
+    checkers: SquareSet,
+    pinned: [SquareSet; 2],
+    pinners: [SquareSet; 2],
+    pub en_passant: Option<Square>,
+    pub castling_rights: CastlingRights,
+    pub halfmove_clock: u32,
+    pub fullmove_number: u32,
+    // Hashes of every position reached so far, ending with the current one
+    pub position_history: Vec<u64>,
 }
 
 impl Default for Board {
     fn default() -> Board {
         Board {
-            array: [Piece::new('-', false); 64],
-            white_pieces: Vec::new(),
-            black_pieces: Vec::new(),
-            white_in_check: false,
-            black_in_check: false,
+            array: [None; 64],
+            pieces: [Vec::new(), Vec::new()],
+            checks: [false; 2],
+            side_to_move: Color::White,
+            checkers: SquareSet::EMPTY,
+            pinned: [SquareSet::EMPTY; 2],
+            pinners: [SquareSet::EMPTY; 2],
+            en_passant: None,
+            castling_rights: CastlingRights::default(),
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            position_history: Vec::new(),
         }
     }
 }
 
 impl Board {
+    // For FENs known to be valid, use from_fen for anything from outside
     pub fn new(fen: &str) -> Board {
-        let fen_vec: Vec<&str> = fen_to_vector(fen);
+        Board::from_fen(fen).unwrap_or_else(|error| panic!("{}: {}", error, fen))
+    }
+
+    // Fields after the piece placement may be left out and take their usual
+    // defaults, but any that are given have to be valid
+    pub fn from_fen(fen: &str) -> Result<Board, FenError> {
+        let fen_vec: Vec<&str> = fen_to_vector(fen)?;
         let mut board = Board::default();
         Self::initialise_board(&mut board, fen_vec);
 
         Self::filter_pieces(&mut board);
-        println!("Black pieces {:?}", board.black_pieces);
-        println!("White pieces {:?}", board.white_pieces);
-        board
+        board.update_checks();
+        board.position_history.push(board.position_hash());
+        log!(Debug, "Black pieces {:?}", board.pieces(Color::Black));
+        log!(Debug, "White pieces {:?}", board.pieces(Color::White));
+        Ok(board)
+    }
+
+    pub fn pieces(&self, color: Color) -> &PieceIndices {
+        &self.pieces[color.index()]
+    }
+
+    pub fn piece_at(&self, square: Square) -> Option<Piece> {
+        self.array[square.index()]
+    }
+
+    pub fn make_move(&mut self, mv: Move) {
+        self.perform_action(Action::from(mv));
     }
 
     pub fn perform_action(&mut self, action: Action) {
+        let is_pawn_move = self
+            .piece_at(action.from)
+            .is_some_and(|piece| piece.piece_type.eq(&PieceType::Pawn));
+        let is_capture = self.piece_at(action.to).is_some();
+
+        let rank_change = action.to.rank() as i32 - action.from.rank() as i32;
+        self.en_passant = match is_pawn_move && rank_change.abs() == 2 {
+            true => action.from.offset(0, rank_change / 2),
+            _ => None,
+        };
+
+        self.halfmove_clock = match is_pawn_move || is_capture {
+            true => 0,
+            _ => self.halfmove_clock + 1,
+        };
+
         self.move_piece_in_array(action.to, action.from);
+        match action.action_type {
+            ActionType::Promote(piece_type) => {
+                if let Some(piece) = self.array[action.to.index()].as_mut() {
+                    piece.piece_type = piece_type;
+                }
+            }
+            // The rook jumps to the square the king passed over
+            ActionType::Castle => {
+                let (rook_from, rook_to) = match action.to > action.from {
+                    true => (action.from.offset(3, 0), action.from.offset(1, 0)),
+                    _ => (action.from.offset(-4, 0), action.from.offset(-1, 0)),
+                };
+                if let (Some(rook_from), Some(rook_to)) = (rook_from, rook_to) {
+                    self.move_piece_in_array(rook_to, rook_from);
+                }
+            }
+            // The captured pawn sits beside the moving pawn, behind the target square
+            ActionType::EnPassant => {
+                if let Some(captured) = Square::new(action.to.file(), action.from.rank()) {
+                    self.array[captured.index()] = None;
+                }
+            }
+            ActionType::Normal => {}
+        }
+        self.castling_rights.update(action.from);
+        self.castling_rights.update(action.to);
+
         self.filter_pieces();
+        if self.side_to_move == Color::Black {
+            self.fullmove_number += 1;
+        }
+        self.side_to_move = self.side_to_move.opposite();
+        self.update_checks();
+        self.position_history.push(self.position_hash());
     }
 
-    fn filter_pieces(&mut self) {
-        let (white_pieces, black_pieces): (PieceIndices, PieceIndices) = self
-            .array
-            .chunks_exact(64)
-            .flat_map(|row| row.iter())
+    pub fn to_fen(&self) -> String {
+        let mut placement = String::new();
+        for rank in (0..8).rev() {
+            let mut empty = 0;
+            for file in 0..8 {
+                let Some(piece) = self.array[rank * 8 + file] else {
+                    empty += 1;
+                    continue;
+                };
+
+                if empty > 0 {
+                    placement.push_str(&empty.to_string());
+                    empty = 0;
+                }
+                placement.push(piece.fen_char());
+            }
+
+            if empty > 0 {
+                placement.push_str(&empty.to_string());
+            }
+            if rank > 0 {
+                placement.push('/');
+            }
+        }
+
+        let side = match self.side_to_move {
+            Color::White => "w",
+            Color::Black => "b",
+        };
+        let en_passant = self
+            .en_passant
+            .map_or("-".to_string(), |square| square.to_string());
+
+        format!(
+            "{} {} {} {} {} {}",
+            placement,
+            side,
+            self.castling_rights.to_fen(),
+            en_passant,
+            self.halfmove_clock,
+            self.fullmove_number
+        )
+    }
+
+    // Resolves long algebraic notation against the legal moves, which fills in
+    // whether it castles, captures en passant or promotes
+    pub fn parse_uci_move(&self, text: &str) -> Result<Action, MoveParseError> {
+        let invalid_format = || MoveParseError::InvalidFormat(text.to_string());
+
+        let from = text.get(0..2).and_then(|square| square.parse().ok());
+        let to = text.get(2..4).and_then(|square| square.parse().ok());
+        let (Some(from), Some(to)) = (from, to) else {
+            return Err(invalid_format());
+        };
+
+        let promotion = match text.get(4..) {
+            Some("") => None,
+            Some(piece) if matches!(piece, "q" | "r" | "b" | "n") => piece
+                .chars()
+                .next()
+                .and_then(|char| PieceType::new_from_char(&char)),
+            _ => return Err(invalid_format()),
+        };
+
+        ActionGenerator::default()
+            .generate_legal_actions(self)
+            .into_iter()
+            .find(|action| {
+                let action_promotion = match action.action_type {
+                    ActionType::Promote(piece_type) => Some(piece_type),
+                    _ => None,
+                };
+                action.from == from && action.to == to && action_promotion == promotion
+            })
+            .ok_or_else(|| MoveParseError::IllegalMove(text.to_string()))
+    }
+
+    pub fn status(&self) -> GameStatus {
+        let legal_actions = ActionGenerator::default().generate_legal_actions(self);
+
+        // Mate takes precedence over the fifty-move rule
+        if legal_actions.is_empty() {
+            return match self.side_to_move_in_check() {
+                true => GameStatus::Checkmate(self.side_to_move.opposite()),
+                _ => GameStatus::Stalemate,
+            };
+        }
+
+        match self.draw_reason() {
+            Some(reason) => GameStatus::Draw(reason),
+            None => GameStatus::Ongoing,
+        }
+    }
+
+    pub fn draw_reason(&self) -> Option<DrawReason> {
+        if self.halfmove_clock >= 100 {
+            Some(DrawReason::FiftyMoveRule)
+        } else if self.repetition_count() >= 3 {
+            Some(DrawReason::ThreefoldRepetition)
+        } else if self.has_insufficient_material() {
+            Some(DrawReason::InsufficientMaterial)
+        } else {
+            None
+        }
+    }
+
+    // How many times the current position has occurred, including now. Only
+    // positions since the last capture or pawn move can repeat.
+    pub fn repetition_count(&self) -> usize {
+        let Some(&current) = self.position_history.last() else {
+            return 0;
+        };
+
+        self.position_history
+            .iter()
+            .rev()
+            .take(self.halfmove_clock as usize + 1)
+            .step_by(2)
+            .filter(|&&hash| hash == current)
+            .count()
+    }
+
+    // Whether the current position already occurred at or after `since`, an
+    // index into position_history. The search uses this to treat a repetition
+    // of a position it reached itself as a draw, which the game would not yet
+    // count as one.
+    pub fn repeats_since(&self, since: usize) -> bool {
+        let Some((&current, earlier)) = self.position_history.split_last() else {
+            return false;
+        };
+
+        earlier
+            .iter()
             .enumerate()
-            .fold((vec![], vec![]), |mut acc, (i, &piece)| {
-                match piece.piece_type {
-                    PieceType::Empty => {}
-                    _ => {
-                        if piece.is_white {
-                            acc.0.push(i as i32);
-                        } else {
-                            acc.1.push(i as i32);
+            .rev()
+            .take(self.halfmove_clock as usize)
+            .skip(1)
+            .step_by(2)
+            .any(|(index, &hash)| index >= since && hash == current)
+    }
+
+    pub fn has_insufficient_material(&self) -> bool {
+        let material = |indices: &PieceIndices| -> Vec<(PieceType, Square)> {
+            indices
+                .iter()
+                .filter_map(|&pos| self.piece_at(pos).map(|piece| (piece.piece_type, pos)))
+                .filter(|(piece_type, _)| !piece_type.eq(&PieceType::King))
+                .collect()
+        };
+        let square_colour = |pos: Square| (pos.file() + pos.rank()) % 2;
+
+        match (
+            material(self.pieces(Color::White)).as_slice(),
+            material(self.pieces(Color::Black)).as_slice(),
+        ) {
+            ([], []) => true,
+            ([(PieceType::Knight | PieceType::Bishop, _)], [])
+            | ([], [(PieceType::Knight | PieceType::Bishop, _)]) => true,
+            ([(PieceType::Bishop, white)], [(PieceType::Bishop, black)]) => {
+                square_colour(*white) == square_colour(*black)
+            }
+            _ => false,
+        }
+    }
+
+    pub fn position_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for piece in self.array {
+            piece
+                .map(|piece| (piece.piece_type, piece.color))
+                .hash(&mut hasher);
+        }
+        self.side_to_move.hash(&mut hasher);
+        // The same position with an en passant square nobody can capture on
+        // still counts as a repetition
+        self.en_passant
+            .filter(|&square| self.can_capture_en_passant(square))
+            .hash(&mut hasher);
+        self.castling_rights.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn can_capture_en_passant(&self, square: Square) -> bool {
+        let behind = match self.side_to_move {
+            Color::White => -1,
+            _ => 1,
+        };
+        [-1, 1]
+            .into_iter()
+            .filter_map(|files| square.offset(files, behind))
+            .any(|from| {
+                self.piece_at(from).is_some_and(|piece| {
+                    piece.piece_type.eq(&PieceType::Pawn) && piece.color == self.side_to_move
+                })
+            })
+    }
+
+    pub fn side_to_move_in_check(&self) -> bool {
+        self.checks[self.side_to_move.index()]
+    }
+
+    pub fn in_check(&self, color: Color) -> bool {
+        self.king_square(color)
+            .is_some_and(|king| self.is_square_attacked(king, color.opposite()))
+    }
+
+    // The enemy pieces giving check to the side to move
+    pub fn checkers(&self) -> SquareSet {
+        self.checkers
+    }
+
+    // Pieces of `color` that can't leave the line between their king and an
+    // enemy slider without exposing the king
+    pub fn pinned(&self, color: Color) -> SquareSet {
+        self.pinned[color.index()]
+    }
+
+    // The enemy sliders holding the pieces of `color` in place
+    pub fn pinners(&self, color: Color) -> SquareSet {
+        self.pinners[color.index()]
+    }
+
+    pub fn king_square(&self, color: Color) -> Option<Square> {
+        self.pieces(color)
+            .iter()
+            .copied()
+            .find(|&pos| self.pos_has_king(pos))
+    }
+
+    fn update_checks(&mut self) {
+        for color in Color::BOTH {
+            self.checks[color.index()] = self.in_check(color);
+            (self.pinned[color.index()], self.pinners[color.index()]) = self.find_pins(color);
+        }
+        self.update_checkers();
+    }
+
+    fn update_checkers(&mut self) {
+        self.checkers = self
+            .king_square(self.side_to_move)
+            .map_or(SquareSet::EMPTY, |king| {
+                self.attackers_to(king, self.side_to_move.opposite())
+            });
+    }
+
+    // Walks out from the king in every direction. A lone friendly piece followed
+    // by an enemy slider moving along that direction is pinned.
+    fn find_pins(&self, color: Color) -> (SquareSet, SquareSet) {
+        let mut pinned = SquareSet::EMPTY;
+        let mut pinners = SquareSet::EMPTY;
+        let Some(king) = self.king_square(color) else {
+            return (pinned, pinners);
+        };
+
+        for direction in Direction::ALL {
+            let (file_step, rank_step) = direction.step();
+            let slider = slider_along(direction);
+            let mut blocker = None;
+            let mut square = king.offset(file_step, rank_step);
+
+            while let Some(current) = square {
+                if let Some(piece) = self.piece_at(current) {
+                    let pins = piece.color != color
+                        && (piece.piece_type.eq(&slider) || piece.piece_type.eq(&PieceType::Queen));
+                    match (blocker, piece.color == color) {
+                        (None, true) => blocker = Some(current),
+                        (Some(blocker), _) if pins => {
+                            pinned.insert(blocker);
+                            pinners.insert(current);
+                            break;
                         }
+                        _ => break,
                     }
                 }
-                acc
+
+                square = current.offset(file_step, rank_step);
+            }
+        }
+
+        (pinned, pinners)
+    }
+
+    // Every piece of `color` that attacks `pos`, whether the square is empty,
+    // holds an enemy or holds one of its own pieces
+    pub fn attackers_to(&self, pos: Square, color: Color) -> SquareSet {
+        let mut attackers = SquareSet::EMPTY;
+        let mut add_if = |square: Option<Square>, attacks: fn(PieceType) -> bool| {
+            if let Some(square) = square {
+                if self
+                    .piece_at(square)
+                    .is_some_and(|piece| piece.color == color && attacks(piece.piece_type))
+                {
+                    attackers.insert(square);
+                }
+            }
+        };
+
+        for square in PRECOMPUTED.knight_targets(pos) {
+            add_if(Some(square), |piece_type| piece_type.eq(&PieceType::Knight));
+        }
+        for square in PRECOMPUTED.king_targets(pos) {
+            add_if(Some(square), |piece_type| piece_type.eq(&PieceType::King));
+        }
+
+        // Pawns capture towards the opposing side, so look back the way they came
+        let pawn_step = match color {
+            Color::White => -1,
+            Color::Black => 1,
+        };
+        for file_step in [-1, 1] {
+            add_if(pos.offset(file_step, pawn_step), |piece_type| {
+                piece_type.eq(&PieceType::Pawn)
             });
+        }
+
+        for direction in Direction::ALL {
+            let (file_step, rank_step) = direction.step();
+            let piece_type = slider_along(direction);
+            let mut square = pos.offset(file_step, rank_step);
+
+            // The first piece along the ray blocks everything behind it
+            while let Some(current) = square {
+                if let Some(target) = self.piece_at(current) {
+                    let attacks = target.piece_type.eq(&piece_type)
+                        || target.piece_type.eq(&PieceType::Queen);
+                    if target.color == color && attacks {
+                        attackers.insert(current);
+                    }
+                    break;
+                }
+
+                square = current.offset(file_step, rank_step);
+            }
+        }
+
+        attackers
+    }
+
+    pub fn is_square_attacked(&self, pos: Square, by: Color) -> bool {
+        !self.attackers_to(pos, by).is_empty()
+    }
+
+    // Passes the turn without moving. Repetitions can't be claimed across a
+    // null move, so the clock is reset until unmake_null_move restores it.
+    pub fn make_null_move(&mut self) -> NullMoveUndo {
+        let undo = NullMoveUndo {
+            en_passant: self.en_passant.take(),
+            halfmove_clock: self.halfmove_clock,
+            checkers: self.checkers,
+        };
+
+        self.side_to_move = self.side_to_move.opposite();
+        self.update_checkers();
+        self.halfmove_clock = 0;
+        self.position_history.push(self.position_hash());
+        undo
+    }
+
+    pub fn unmake_null_move(&mut self, undo: NullMoveUndo) {
+        self.position_history.pop();
+        self.side_to_move = self.side_to_move.opposite();
+        self.en_passant = undo.en_passant;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.checkers = undo.checkers;
+    }
+
+    pub fn has_non_pawn_material(&self, color: Color) -> bool {
+        self.pieces(color).iter().any(|&pos| {
+            self.piece_at(pos)
+                .is_some_and(|piece| !matches!(piece.piece_type, PieceType::Pawn | PieceType::King))
+        })
+    }
+
+    fn filter_pieces(&mut self) {
+        let mut pieces: [PieceIndices; 2] = [Vec::new(), Vec::new()];
+        for square in Square::all() {
+            if let Some(piece) = self.piece_at(square) {
+                pieces[piece.color.index()].push(square);
+            }
+        }
 
-        self.white_pieces = white_pieces;
-        self.black_pieces = black_pieces;
+        self.pieces = pieces;
     }
 
-    fn move_piece_in_array(&mut self, to: i32, from: i32) {
-        let piece_to_move = self.array[from as usize];
-        self.array[from as usize] = Piece::new_empty();
-        self.array[to as usize] = piece_to_move;
+    fn move_piece_in_array(&mut self, to: Square, from: Square) {
+        let mut piece_to_move = self.array[from.index()].take();
+        if let Some(piece) = piece_to_move.as_mut() {
+            piece.has_moved = true;
+        }
+        self.array[to.index()] = piece_to_move;
     }
 
     fn initialise_board(board: &mut Board, fen_vec: Vec<&str>) {
@@ -87,35 +658,545 @@ impl Board {
             } else {
                 let i = rank * 8 + file;
 
-                let piece = Piece::new(char, false);
+                // Pawns away from their starting rank have lost their double push
+                let start_rank = match char.is_ascii_uppercase() {
+                    true => 1,
+                    _ => 6,
+                };
+                let has_moved = char.eq_ignore_ascii_case(&'p') && rank != start_rank;
+
+                let piece = Piece::from_char(char).map(|piece| Piece { has_moved, ..piece });
                 board.array[i as usize] = piece;
                 file += 1;
             }
         }
+
+        board.side_to_move = match fen_vec.get(1) {
+            Some(&"b") => Color::Black,
+            _ => Color::White,
+        };
+        board.castling_rights = fen_vec
+            .get(2)
+            .map_or(CastlingRights::default(), |&castling| {
+                CastlingRights::new(castling)
+            });
+        board.en_passant = fen_vec.get(3).and_then(|&square| square.parse().ok());
+        board.halfmove_clock = fen_vec
+            .get(4)
+            .and_then(|&clock| clock.parse().ok())
+            .unwrap_or(0);
+        board.fullmove_number = fen_vec
+            .get(5)
+            .and_then(|&number| number.parse().ok())
+            .unwrap_or(1);
     }
 
-    pub fn print(&self) {
-        let mut board = self.array;
-        board.reverse();
-        for rank in 0..8 {
-            let mut row = String::from("");
-            for file in 0..8 {
-                let i = rank * 8 + file;
-                let piece = board[i];
+    pub fn pos_has_king(&self, pos: Square) -> bool {
+        self.piece_at(pos).is_some_and(|piece| piece.is_king())
+    }
+}
 
-                // row = format!("{} {} {:?} ", row, i, piece.get_char());
-                row = format!("{} {:?} ", row, piece.get_char());
-            }
-            println!("{}", row);
+// Besides the queen, the piece that slides along this direction
+fn slider_along(direction: Direction) -> PieceType {
+    match direction.is_diagonal() {
+        true => PieceType::Bishop,
+        _ => PieceType::Rook,
+    }
+}
+
+fn fen_to_vector(fen: &str) -> Result<Vec<&str>, FenError> {
+    let fen_vec: Vec<&str> = fen.split_whitespace().collect();
+
+    let placement = *fen_vec.first().ok_or(FenError::MissingPlacement)?;
+    let ranks: Vec<&str> = placement.split('/').collect();
+    let is_valid_rank = |rank: &&str| {
+        let mut files = 0;
+        for char in rank.chars() {
+            files += match char.to_digit(10) {
+                Some(empty @ 1..=8) => empty,
+                None if Piece::from_char(char).is_some() => 1,
+                _ => return false,
+            };
         }
+        files == 8
+    };
+    if ranks.len() != 8 || !ranks.iter().all(is_valid_rank) {
+        return Err(FenError::InvalidPlacement(placement.to_string()));
     }
 
-    pub fn pos_has_king(&self, pos: i32) -> bool {
-        self.array[pos as usize].is_king()
+    if let Some(&side) = fen_vec.get(1).filter(|&&side| side != "w" && side != "b") {
+        return Err(FenError::InvalidSideToMove(side.to_string()));
     }
+
+    if let Some(&castling) = fen_vec.get(2).filter(|&&castling| castling != "-") {
+        // Each right at most once, in the usual KQkq order
+        let mut rights = "KQkq".chars();
+        if !castling
+            .chars()
+            .all(|char| rights.any(|right| right == char))
+        {
+            return Err(FenError::InvalidCastling(castling.to_string()));
+        }
+    }
+
+    if let Some(&en_passant) = fen_vec.get(3).filter(|&&en_passant| en_passant != "-") {
+        let is_valid = en_passant
+            .parse::<Square>()
+            .is_ok_and(|square| square.rank() == 2 || square.rank() == 5);
+        if !is_valid {
+            return Err(FenError::InvalidEnPassant(en_passant.to_string()));
+        }
+    }
+
+    if let Some(&counter) = fen_vec
+        .iter()
+        .skip(4)
+        .find(|counter| counter.parse::<u32>().is_err())
+    {
+        return Err(FenError::InvalidMoveCounter(counter.to_string()));
+    }
+
+    Ok(fen_vec)
 }
 
-fn fen_to_vector(fen: &str) -> Vec<&str> {
-    // TODO: Validate fen
-    fen.split_whitespace().collect()
+#[cfg(test)]
+mod tests {
+    use super::{Board, CastlingRights, DrawReason, FenError, GameStatus, DEFAULT_FEN};
+    use crate::square::{
+        Square, A1, A2, A5, A8, C4, C8, D1, D2, D4, D5, D6, D8, E1, E2, E3, E4, E5, E7, E8, F1, F3,
+        F6, F8, G1, G8, H1, H8,
+    };
+    use crate::{
+        action::{Action, ActionType, MoveParseError},
+        piece::{Color, PieceType},
+    };
+
+    #[test]
+    fn to_fen_round_trips() {
+        let fens = [
+            DEFAULT_FEN,
+            "r3k2r/1P6/8/3pP3/8/5N2/8/R3K2R w Kq d6 0 17",
+            "4k3/8/8/8/4Pp2/8/8/4K3 b - e3 3 40",
+        ];
+        for fen in fens {
+            assert_eq!(Board::new(fen).to_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn fullmove_number_advances_after_black_moves() {
+        let mut board = Board::new(DEFAULT_FEN);
+
+        board.perform_action(Action::new(E2, E4, None));
+        assert_eq!(board.fullmove_number, 1);
+        board.perform_action(Action::new(E7, E5, None));
+        assert_eq!(board.fullmove_number, 2);
+    }
+
+    #[test]
+    fn parses_side_to_move_and_en_passant() {
+        let board = Board::new("4k3/8/8/8/4Pp2/8/8/4K3 b - e3 0 1");
+
+        assert_eq!(board.side_to_move, Color::Black);
+        assert_eq!(board.en_passant, Some(E3));
+    }
+
+    #[test]
+    fn from_fen_rejects_malformed_fields() {
+        let errors = [
+            ("", FenError::MissingPlacement),
+            (
+                "kkkkkkkkk/8/8/8/8/8/8/K7 w - - 0 1",
+                FenError::InvalidPlacement("kkkkkkkkk/8/8/8/8/8/8/K7".to_string()),
+            ),
+            (
+                "4k3/8/8/8/8/8/4K3 w - - 0 1",
+                FenError::InvalidPlacement("4k3/8/8/8/8/8/4K3".to_string()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4X3 w - - 0 1",
+                FenError::InvalidPlacement("4k3/8/8/8/8/8/8/4X3".to_string()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 x - - 0 1",
+                FenError::InvalidSideToMove("x".to_string()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w KK - 0 1",
+                FenError::InvalidCastling("KK".to_string()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - e4 0 1",
+                FenError::InvalidEnPassant("e4".to_string()),
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - - x 1",
+                FenError::InvalidMoveCounter("x".to_string()),
+            ),
+        ];
+        for (fen, error) in errors {
+            assert_eq!(Board::from_fen(fen).err(), Some(error));
+        }
+
+        assert!(Board::from_fen("4k3/8/8/8/8/8/8/4K3").is_ok());
+        assert!(Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R b Kq - 3 20").is_ok());
+    }
+
+    #[test]
+    fn null_move_flips_side_and_restores_en_passant() {
+        let mut board = Board::new("4k3/8/8/8/4Pp2/8/8/4K3 b - e3 7 1");
+
+        let undo = board.make_null_move();
+        assert_eq!(board.side_to_move, Color::White);
+        assert_eq!(board.en_passant, None);
+
+        board.unmake_null_move(undo);
+        assert_eq!(board.side_to_move, Color::Black);
+        assert_eq!(board.en_passant, Some(E3));
+        assert_eq!(board.halfmove_clock, 7);
+        assert_eq!(board.position_history.len(), 1);
+    }
+
+    #[test]
+    fn double_push_sets_en_passant() {
+        let mut board = Board::new("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+
+        board.perform_action(crate::action::Action::new(E2, E4, None));
+
+        assert_eq!(board.en_passant, Some(E3));
+        assert_eq!(board.side_to_move, Color::Black);
+    }
+
+    #[test]
+    fn has_non_pawn_material_ignores_king_and_pawns() {
+        let board = Board::new("4k3/pppp4/8/8/8/8/4P3/3NK3 w - - 0 1");
+
+        assert!(board.has_non_pawn_material(Color::White));
+        assert!(!board.has_non_pawn_material(Color::Black));
+    }
+
+    #[test]
+    fn king_in_check_white_complex_no_check() {
+        let board = Board::new("b2r2b1/1B3B2/3R4/2RK2Rr/r3B3/1B1R1b2/8/3r4 w - - 0 1");
+        assert!(!board.in_check(Color::White));
+    }
+
+    #[test]
+    fn king_in_check_white_rook_w() {
+        let board = Board::new("8/8/8/8/8/8/1r2K3/8 w - - 0 1");
+        assert!(board.in_check(Color::White));
+    }
+
+    #[test]
+    fn king_in_check_white_bishop_nw() {
+        let board = Board::new("8/8/8/8/b7/8/2K5/8 w - - 0 1");
+        assert!(board.in_check(Color::White));
+    }
+
+    #[test]
+    fn king_in_check_white_bishop_ne() {
+        let board = Board::new("8/8/8/5b2/8/8/2K5/8 w - - 0 1");
+        assert!(board.in_check(Color::White));
+    }
+
+    #[test]
+    fn king_in_check_white_rook_s() {
+        let board = Board::new("8/8/8/8/8/8/4K3/4r3 w - - 0 1");
+        assert!(board.in_check(Color::White));
+    }
+
+    #[test]
+    fn attackers_to_finds_every_piece_type() {
+        let board = Board::new("4k3/8/8/3p4/2P5/4N3/B7/3RK3 w - - 0 1");
+
+        let attackers: Vec<_> = board.attackers_to(D5, Color::White).collect();
+        assert_eq!(attackers, vec![D1, E3, C4]);
+        assert_eq!(
+            board.attackers_to(C4, Color::White).collect::<Vec<_>>(),
+            vec![A2, E3]
+        );
+        assert_eq!(board.attackers_to(E2, Color::White).len(), 1);
+        assert!(board.attackers_to(D5, Color::Black).is_empty());
+        assert!(board.is_square_attacked(C4, Color::Black));
+    }
+
+    #[test]
+    fn finds_pinned_pieces_and_their_pinners() {
+        let board = Board::new("4r1k1/8/8/b7/8/8/3PB3/4K3 w - - 0 1");
+
+        assert_eq!(board.pinned(Color::White).collect::<Vec<_>>(), vec![D2, E2]);
+        assert_eq!(
+            board.pinners(Color::White).collect::<Vec<_>>(),
+            vec![A5, E8]
+        );
+        assert!(board.pinned(Color::Black).is_empty());
+
+        // Two pieces on the line shield each other
+        let board = Board::new("4r1k1/8/8/8/4N3/8/4B3/4K3 w - - 0 1");
+        assert!(board.pinned(Color::White).is_empty());
+    }
+
+    #[test]
+    fn checkers_follow_the_side_to_move() {
+        let mut board = Board::new("4k3/8/8/8/8/8/8/R3K1N1 w - - 0 1");
+        assert!(board.checkers().is_empty());
+
+        board.perform_action(Action::new(A1, A8, None));
+        assert_eq!(board.checkers().collect::<Vec<_>>(), vec![A8]);
+
+        let board = Board::new("4k3/8/5N2/8/8/8/8/4R1K1 b - - 0 1");
+        assert_eq!(board.checkers().len(), 2);
+    }
+
+    #[test]
+    fn empty_squares_are_not_black_pieces() {
+        let board = Board::new("4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+
+        assert_eq!(board.piece_at(E4), None);
+        assert!(!board.is_square_attacked(E3, Color::White));
+        assert!(!board.is_square_attacked(D4, Color::Black));
+        assert!(board.is_square_attacked(D2, Color::White));
+    }
+
+    #[test]
+    fn king_in_check_black_by_knight_pawn_and_queen() {
+        assert!(Board::new("4k3/8/5N2/8/8/8/8/4K3 b - - 0 1").in_check(Color::Black));
+        assert!(Board::new("4k3/3P4/8/8/8/8/8/4K3 b - - 0 1").in_check(Color::Black));
+        assert!(Board::new("4k3/8/8/8/Q7/8/8/4K3 b - - 0 1").in_check(Color::Black));
+        assert!(!Board::new("4k3/4P3/8/8/8/8/8/4K3 b - - 0 1").in_check(Color::Black));
+    }
+
+    #[test]
+    fn check_flags_are_updated_after_each_move() {
+        let mut board = Board::new("4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+        assert!(!board.in_check(Color::Black));
+
+        board.perform_action(crate::action::Action::new(A1, A8, None));
+
+        assert!(board.in_check(Color::Black));
+        assert!(!board.in_check(Color::White));
+    }
+
+    #[test]
+    fn status_detects_checkmate() {
+        let board = Board::new("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1");
+        assert_eq!(board.status(), GameStatus::Checkmate(Color::White));
+    }
+
+    #[test]
+    fn status_detects_stalemate() {
+        let board = Board::new("7k/5Q2/8/8/8/8/8/6K1 b - - 0 1");
+        assert_eq!(board.status(), GameStatus::Stalemate);
+    }
+
+    #[test]
+    fn status_is_ongoing_with_legal_moves() {
+        let board = Board::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(board.status(), GameStatus::Ongoing);
+    }
+
+    #[test]
+    fn halfmove_clock_resets_on_pawn_moves_and_captures() {
+        let mut board = Board::new("4k3/8/8/3p4/8/8/4P3/R3K3 w - - 12 30");
+        assert_eq!(board.halfmove_clock, 12);
+
+        board.perform_action(Action::new(A1, A2, None));
+        assert_eq!(board.halfmove_clock, 13);
+
+        board.perform_action(Action::new(D5, D4, None));
+        assert_eq!(board.halfmove_clock, 0);
+
+        board.perform_action(Action::new(A2, D2, None));
+        board.perform_action(Action::new(E8, D8, None));
+        board.perform_action(Action::new(D2, D4, None));
+        assert_eq!(board.halfmove_clock, 0);
+    }
+
+    #[test]
+    fn status_detects_fifty_move_rule() {
+        let board = Board::new("4k3/8/8/8/8/8/8/R3K3 w - - 100 80");
+        assert_eq!(board.status(), GameStatus::Draw(DrawReason::FiftyMoveRule));
+    }
+
+    #[test]
+    fn status_detects_threefold_repetition() {
+        let mut board = Board::new("4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+        let shuffle = [(A1, A2), (E8, F8), (A2, A1), (F8, E8)];
+
+        for _ in 0..2 {
+            assert_eq!(board.status(), GameStatus::Ongoing);
+            for (from, to) in shuffle {
+                board.perform_action(Action::new(from, to, None));
+            }
+        }
+
+        assert_eq!(board.repetition_count(), 3);
+        assert_eq!(
+            board.status(),
+            GameStatus::Draw(DrawReason::ThreefoldRepetition)
+        );
+    }
+
+    #[test]
+    fn en_passant_squares_only_count_when_a_pawn_can_capture() {
+        let mut board = Board::new("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+        let shuffle = [(E8, D8), (E1, D1), (D8, E8), (D1, E1)];
+        board.perform_action(Action::new(E2, E4, None));
+        for _ in 0..2 {
+            for (from, to) in shuffle {
+                board.perform_action(Action::new(from, to, None));
+            }
+        }
+        assert_eq!(board.repetition_count(), 3);
+
+        let capturable = Board::new("4k3/8/8/8/3pP3/8/8/4K3 b - e3 0 1");
+        let without = Board::new("4k3/8/8/8/3pP3/8/8/4K3 b - - 0 1");
+        assert_ne!(capturable.position_hash(), without.position_hash());
+    }
+
+    #[test]
+    fn repeats_since_ignores_repetitions_before_the_given_index() {
+        let mut board = Board::new("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let play = |board: &mut Board, moves: &[(Square, Square)]| {
+            for &(from, to) in moves {
+                board.perform_action(Action::new(from, to, None));
+            }
+        };
+
+        play(&mut board, &[(G1, F3), (G8, F6), (F3, G1), (F6, G8)]);
+        assert!(board.repeats_since(0));
+        assert_eq!(board.draw_reason(), None);
+
+        // The knight shuffle from here repeats positions seen only before this point
+        play(&mut board, &[(G1, F3), (G8, F6)]);
+        let root = board.position_history.len() - 1;
+        play(&mut board, &[(F3, G1)]);
+        assert!(!board.repeats_since(root));
+        assert_eq!(board.draw_reason(), None);
+
+        play(&mut board, &[(F6, G8)]);
+        assert_eq!(board.draw_reason(), Some(DrawReason::ThreefoldRepetition));
+    }
+
+    #[test]
+    fn status_detects_insufficient_material() {
+        let draws = [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4KN2 w - - 0 1",
+            "4kb2/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1",
+        ];
+        for fen in draws {
+            assert_eq!(
+                Board::new(fen).status(),
+                GameStatus::Draw(DrawReason::InsufficientMaterial)
+            );
+        }
+
+        assert!(!Board::new("4kb2/8/8/8/8/8/8/3BK3 w - - 0 1").has_insufficient_material());
+        assert!(!Board::new("4k3/8/8/8/8/8/8/3NKN2 w - - 0 1").has_insufficient_material());
+        assert!(!Board::new("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1").has_insufficient_material());
+    }
+
+    #[test]
+    fn castling_moves_the_rook_and_clears_rights() {
+        let mut board = Board::new("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+
+        board.perform_action(Action::new(E1, G1, Some(ActionType::Castle)));
+
+        assert!(board
+            .piece_at(F1)
+            .is_some_and(|piece| piece.piece_type.eq(&PieceType::Rook)));
+        assert_eq!(board.piece_at(H1), None);
+        assert!(!board.castling_rights.white_king_side);
+        assert!(!board.castling_rights.white_queen_side);
+        assert!(board.castling_rights.black_queen_side);
+
+        board.perform_action(Action::new(E8, C8, Some(ActionType::Castle)));
+
+        assert!(board
+            .piece_at(D8)
+            .is_some_and(|piece| piece.piece_type.eq(&PieceType::Rook)));
+        assert_eq!(board.piece_at(A8), None);
+        assert_eq!(board.castling_rights, CastlingRights::default());
+    }
+
+    #[test]
+    fn capturing_a_rook_clears_its_castling_right() {
+        let mut board = Board::new("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+
+        board.perform_action(Action::new(H1, H8, None));
+
+        assert!(!board.castling_rights.black_king_side);
+        assert!(!board.castling_rights.white_king_side);
+        assert!(board.castling_rights.black_queen_side);
+    }
+
+    #[test]
+    fn en_passant_removes_the_captured_pawn() {
+        let mut board = Board::new("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+
+        board.perform_action(Action::new(E5, D6, Some(ActionType::EnPassant)));
+
+        assert_eq!(board.piece_at(D5), None);
+        assert!(board
+            .piece_at(D6)
+            .is_some_and(|piece| piece.piece_type.eq(&PieceType::Pawn)));
+        assert_eq!(board.pieces(Color::Black).len(), 1);
+    }
+
+    #[test]
+    fn parses_default_castling_rights() {
+        let board = Board::new(DEFAULT_FEN);
+
+        assert!(board.castling_rights.white_king_side);
+        assert!(board.castling_rights.black_queen_side);
+    }
+
+    #[test]
+    fn parse_uci_move_resolves_action_types() {
+        let board = Board::new("r3k3/1P6/8/3pP3/8/8/8/R3K2R w KQq d6 0 1");
+
+        let castle = board.parse_uci_move("e1g1").unwrap();
+        let en_passant = board.parse_uci_move("e5d6").unwrap();
+        let promotion = board.parse_uci_move("b7a8n").unwrap();
+        let quiet = board.parse_uci_move("a1a7").unwrap();
+
+        assert_eq!(castle.action_type, ActionType::Castle);
+        assert_eq!(en_passant.action_type, ActionType::EnPassant);
+        assert_eq!(
+            promotion.action_type,
+            ActionType::Promote(PieceType::Knight)
+        );
+        assert_eq!(quiet.action_type, ActionType::Normal);
+    }
+
+    #[test]
+    fn parse_uci_move_rejects_bad_input() {
+        let board = Board::new(DEFAULT_FEN);
+
+        assert_eq!(
+            board.parse_uci_move("e2e5"),
+            Err(MoveParseError::IllegalMove("e2e5".to_string()))
+        );
+        assert_eq!(
+            board.parse_uci_move("e7e8"),
+            Err(MoveParseError::IllegalMove("e7e8".to_string()))
+        );
+        for text in ["", "e2", "e2e9", "i2i4", "e2e4k", "e2e4qq"] {
+            assert_eq!(
+                board.parse_uci_move(text),
+                Err(MoveParseError::InvalidFormat(text.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn to_uci_round_trips_through_parse_uci_move() {
+        let board = Board::new("r3k3/1P6/8/3pP3/8/8/8/R3K2R w KQq d6 0 1");
+
+        for text in ["e1g1", "e1c1", "e5d6", "b7b8q", "b7a8r", "h1h8"] {
+            assert_eq!(board.parse_uci_move(text).unwrap().to_uci(), text);
+        }
+    }
 }
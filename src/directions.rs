@@ -1,87 +1,99 @@
-use crate::piece::PieceType;
+use crate::piece::{Color, PieceType};
 
-pub const EAST: i32 = 1;
-pub const WEST: i32 = -1;
-
-pub const NORTH: i32 = -8;
-pub const NORTH_WEST: i32 = NORTH + WEST;
-pub const NORTH_EAST: i32 = NORTH + EAST;
-
-pub const SOUTH: i32 = 8;
-pub const SOUTH_WEST: i32 = SOUTH + WEST;
-pub const SOUTH_EAST: i32 = SOUTH + EAST;
+// Compass directions, with north pointing towards the eighth rank. The
+// discriminants index the per-direction tables in PrecomputedData.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
 
-pub const DIRECTIONS: [&str; 8] = [
-    "NORTH",
-    "NORTH_EAST",
-    "EAST",
-    "SOUTH_EAST",
-    "SOUTH",
-    "SOUTH_WEST",
-    "WEST",
-    "NORTH_WEST",
-];
+impl Direction {
+    pub const ALL: [Direction; 8] = [
+        Direction::North,
+        Direction::NorthEast,
+        Direction::East,
+        Direction::SouthEast,
+        Direction::South,
+        Direction::SouthWest,
+        Direction::West,
+        Direction::NorthWest,
+    ];
+    pub const ORTHOGONAL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+    pub const DIAGONAL: [Direction; 4] = [
+        Direction::NorthEast,
+        Direction::SouthEast,
+        Direction::SouthWest,
+        Direction::NorthWest,
+    ];
 
-#[derive(Debug, Clone, Copy)]
-pub struct Direction<'a> {
-    pub name: &'a str,
-    pub offset: i32,
-    pub limit: i32,
-}
+    pub const fn index(self) -> usize {
+        self as usize
+    }
 
-impl<'a> Direction<'a> {
-    fn new(name: &'a str, offset: i32, limit: i32) -> Self {
-        Direction {
-            name,
-            offset,
-            limit,
+    // (file, rank) step
+    pub const fn step(self) -> (i32, i32) {
+        match self {
+            Direction::North => (0, 1),
+            Direction::NorthEast => (1, 1),
+            Direction::East => (1, 0),
+            Direction::SouthEast => (1, -1),
+            Direction::South => (0, -1),
+            Direction::SouthWest => (-1, -1),
+            Direction::West => (-1, 0),
+            Direction::NorthWest => (-1, 1),
         }
     }
-}
 
-pub fn allowed_directions(piece_type: PieceType, is_white: bool) -> Vec<Direction<'static>> {
-    let rook: Vec<Direction> = vec![
-        Direction::new("NORTH", NORTH, 8),
-        Direction::new("EAST", EAST, 8),
-        Direction::new("SOUTH", SOUTH, 8),
-        Direction::new("WEST", WEST, 8),
-    ];
+    pub const fn is_diagonal(self) -> bool {
+        self.index() % 2 == 1
+    }
+}
 
-    let bishop = vec![
-        Direction::new("NORTH_EAST", NORTH_EAST, 8),
-        Direction::new("NORTH_WEST", NORTH_WEST, 8),
-        Direction::new("SOUTH_EAST", SOUTH_EAST, 8),
-        Direction::new("SOUTH_WEST", SOUTH_WEST, 8),
-    ];
+// (file, rank) jumps, clockwise from north-north-east
+pub const KNIGHT_STEPS: [(i32, i32); 8] = [
+    (1, 2),
+    (2, 1),
+    (2, -1),
+    (1, -2),
+    (-1, -2),
+    (-2, -1),
+    (-2, 1),
+    (-1, 2),
+];
 
+// The directions a piece slides along until blocked. Pawns, knights and kings
+// step instead and have their own tables.
+pub fn sliding_directions(piece_type: PieceType) -> &'static [Direction] {
     match piece_type {
-        PieceType::Pawn => match is_white {
-            false => vec![
-                Direction::new("NORTH", NORTH, 2),
-                Direction::new("NORTH_EAST", NORTH_EAST, 1),
-                Direction::new("NORTH_WEST", NORTH_WEST, 1),
-            ],
-            true => vec![
-                Direction::new("SOUTH", SOUTH, 2),
-                Direction::new("SOUTH_EAST", SOUTH_EAST, 1),
-                Direction::new("SOUTH_WEST", SOUTH_WEST, 1),
-            ],
-        },
-        // using rook here then just manually offsetting and limiting
-        PieceType::Knight => rook,
-        PieceType::Bishop => bishop,
-        PieceType::Rook => rook,
-        PieceType::Queen => [&bishop[..], &rook[..]].concat(),
-        PieceType::King => vec![
-            Direction::new("NORTH", NORTH, 1),
-            Direction::new("EAST", EAST, 1),
-            Direction::new("SOUTH", SOUTH, 1),
-            Direction::new("WEST", WEST, 1),
-            Direction::new("NORTH_EAST", NORTH_EAST, 1),
-            Direction::new("NORTH_WEST", NORTH_WEST, 1),
-            Direction::new("SOUTH_EAST", SOUTH_EAST, 1),
-            Direction::new("SOUTH_WEST", SOUTH_WEST, 1),
-        ],
-        PieceType::Empty => vec![],
+        PieceType::Bishop => &Direction::DIAGONAL,
+        PieceType::Rook => &Direction::ORTHOGONAL,
+        PieceType::Queen => &Direction::ALL,
+        _ => &[],
+    }
+}
+
+// The way a pawn advances, and the two diagonals it captures along
+pub fn pawn_directions(color: Color) -> (Direction, [Direction; 2]) {
+    match color {
+        Color::White => (
+            Direction::North,
+            [Direction::NorthEast, Direction::NorthWest],
+        ),
+        Color::Black => (
+            Direction::South,
+            [Direction::SouthEast, Direction::SouthWest],
+        ),
     }
 }
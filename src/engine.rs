@@ -1,46 +1,567 @@
-use crate::{action::Action, action_generator::ActionGenerator, board::Board};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{
+    action::Action,
+    action_generator::ActionGenerator,
+    board::Board,
+    evaluation::{evaluate, MATE_BOUND, MATE_SCORE},
+    log,
+    move_picker::{order_score, MovePicker},
+    moves::Move,
+    piece::Color,
+};
+
+pub const MAX_PLY: usize = 64;
+
+const NULL_MOVE_MIN_DEPTH: i32 = 3;
+const NULL_MOVE_VERIFICATION_DEPTH: i32 = 6;
+
+const LMR_MIN_DEPTH: i32 = 3;
+const LMR_FULL_DEPTH_MOVES: usize = 3;
+
+const ASPIRATION_MIN_DEPTH: i32 = 4;
+const ASPIRATION_WINDOW: i32 = 25;
+// Past this the window is abandoned for a full width search
+const ASPIRATION_MAX_DELTA: i32 = 1_000;
+
+// Leaves the GUI time to receive the move before the clock runs out
+pub const MOVE_OVERHEAD: Duration = Duration::from_millis(50);
+const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+// How often (in nodes) the clock is checked while searching
+const TIME_CHECK_INTERVAL: i64 = 1024;
+
+pub type InfoCallback = Box<dyn FnMut(&SearchInfo) + Send>;
 
 pub struct Engine {
     board: Board,
     generator: ActionGenerator,
     max_depth: i32,
     nodes: i64,
+    null_move_verification: bool,
+    time_limit: Option<Duration>,
+    started_at: Instant,
+    // The time limit counts from here rather than from the start of the search,
+    // since time spent pondering is the opponent's
+    clock_started_at: Instant,
+    stopped: bool,
+    killers: Vec<[Option<Move>; 2]>,
+    info_callback: Option<InfoCallback>,
+    signals: Arc<SearchSignals>,
+    node_limit: Option<i64>,
+    search_actions: Vec<Action>,
+}
+
+// Lets another thread steer a running search. The front end is responsible for
+// clearing `stop` before starting the next one.
+#[derive(Debug, Default)]
+pub struct SearchSignals {
+    pub stop: AtomicBool,
+    // The time limit only starts counting once pondering ends
+    pub ponder: AtomicBool,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SearchResult {
+    pub best_action: Option<Action>,
+    pub score: i32,
+    pub depth: i32,
+    pub nodes: i64,
+    pub time: Duration,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SearchInfo {
+    // An iteration of iterative deepening completed
+    Iteration(SearchResult),
+    // The score at the root fell outside the aspiration window and is re-searched
+    FailHigh { depth: i32, score: i32, nodes: i64 },
+    FailLow { depth: i32, score: i32, nodes: i64 },
 }
 
 impl Engine {
     pub fn new(fen: &str) -> Self {
-        let board = Board::new(fen);
+        Engine::from_board(Board::new(fen))
+    }
+
+    pub fn from_board(board: Board) -> Self {
         let generator = ActionGenerator::default();
-        board.print();
+        log!(Debug, "Engine created for {}", board.to_fen());
         Engine {
             board,
             generator,
             max_depth: 4,
             nodes: 0,
+            null_move_verification: true,
+            time_limit: None,
+            started_at: Instant::now(),
+            clock_started_at: Instant::now(),
+            stopped: false,
+            killers: vec![[None, None]; MAX_PLY],
+            info_callback: None,
+            signals: Arc::new(SearchSignals::default()),
+            node_limit: None,
+            search_actions: Vec::new(),
         }
     }
 
-    pub fn move_piece(&self) {
-        let mut new_board = self.board.clone();
-        new_board.perform_action(Action::new(0, 32, None));
-        new_board.print();
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn set_board(&mut self, board: Board) {
+        self.board = board;
+    }
+
+    pub fn signals(&self) -> Arc<SearchSignals> {
+        Arc::clone(&self.signals)
+    }
+
+    pub fn set_node_limit(&mut self, node_limit: Option<i64>) {
+        self.node_limit = node_limit;
+    }
+
+    // Restricts the root to these actions. An empty list searches everything.
+    pub fn set_search_actions(&mut self, search_actions: Vec<Action>) {
+        self.search_actions = search_actions;
+    }
+
+    pub fn set_info_callback(&mut self, info_callback: Option<InfoCallback>) {
+        self.info_callback = info_callback;
+    }
+
+    pub fn set_max_depth(&mut self, max_depth: i32) {
+        self.max_depth = max_depth;
+    }
+
+    pub fn set_null_move_verification(&mut self, enabled: bool) {
+        self.null_move_verification = enabled;
+    }
+
+    pub fn set_time_limit(&mut self, time_limit: Option<Duration>) {
+        self.time_limit = time_limit;
     }
 
     pub fn evaluate(&mut self) {
-        Self::recurse_moves(self, &mut self.board.to_owned(), true, 0);
+        let color = self.board.side_to_move;
+        Self::recurse_moves(self, &mut self.board.to_owned(), color, 0);
+    }
+
+    pub fn search(&mut self) -> SearchResult {
+        self.nodes = 0;
+        self.started_at = Instant::now();
+        self.clock_started_at = self.started_at;
+        self.stopped = false;
+        self.killers = vec![[None, None]; MAX_PLY];
+
+        let mut result = SearchResult {
+            best_action: None,
+            score: 0,
+            depth: 0,
+            nodes: 0,
+            time: Duration::ZERO,
+        };
+
+        for depth in 1..=self.max_depth {
+            let (best_action, score) = self.search_aspiration(depth, &result);
+
+            // A partial iteration is only better than nothing
+            if self.stopped && result.best_action.is_some() {
+                break;
+            }
+
+            result = SearchResult {
+                best_action,
+                score,
+                depth,
+                nodes: self.nodes,
+                time: self.started_at.elapsed(),
+            };
+
+            if self.stopped {
+                break;
+            }
+
+            self.report(SearchInfo::Iteration(result.clone()));
+        }
+
+        // Stopped before any move was scored, but any legal move beats none
+        if result.best_action.is_none() {
+            result.best_action = self
+                .generator
+                .generate_legal_actions(&self.board)
+                .into_iter()
+                .next();
+        }
+
+        result
+    }
+
+    // Searches the root with a narrow window around the previous iteration's
+    // score, widening it on each side that fails until the score fits.
+    fn search_aspiration(&mut self, depth: i32, previous: &SearchResult) -> (Option<Action>, i32) {
+        let mut pv_action = previous.best_action.clone();
+
+        if depth < ASPIRATION_MIN_DEPTH || previous.score.abs() >= MATE_BOUND {
+            return self.search_root(depth, pv_action, -MATE_SCORE, MATE_SCORE);
+        }
+
+        let mut delta = ASPIRATION_WINDOW;
+        let mut alpha = previous.score - delta;
+        let mut beta = previous.score + delta;
+
+        loop {
+            let (best_action, score) = self.search_root(depth, pv_action.clone(), alpha, beta);
+
+            if self.stopped {
+                return (best_action, score);
+            }
+
+            delta += delta / 2;
+            let give_up = delta > ASPIRATION_MAX_DELTA;
+
+            if score <= alpha {
+                self.report(SearchInfo::FailLow {
+                    depth,
+                    score,
+                    nodes: self.nodes,
+                });
+                alpha = match give_up {
+                    true => -MATE_SCORE,
+                    _ => (score - delta).max(-MATE_SCORE),
+                };
+            } else if score >= beta {
+                self.report(SearchInfo::FailHigh {
+                    depth,
+                    score,
+                    nodes: self.nodes,
+                });
+                beta = match give_up {
+                    true => MATE_SCORE,
+                    _ => (score + delta).min(MATE_SCORE),
+                };
+                pv_action = best_action;
+            } else {
+                return (best_action, score);
+            }
+        }
+    }
+
+    fn search_root(
+        &mut self,
+        depth: i32,
+        pv_action: Option<Action>,
+        mut alpha: i32,
+        beta: i32,
+    ) -> (Option<Action>, i32) {
+        let board = self.board.clone();
+        let mut moves = self.generator.generate_moves(&board, board.side_to_move);
+        if !self.search_actions.is_empty() {
+            moves.retain(|&mv| self.search_actions.contains(&Action::from(mv)));
+        }
+        let pv_move = pv_action.map(|action| Move::from_action(&action, &board));
+        self.order_moves(&board, &mut moves, 0, pv_move);
+
+        let mut best_score = -MATE_SCORE;
+        let mut best_action = None;
+        let mut searched = 0;
+
+        for &mv in moves.iter() {
+            let mut board_after_move = board.clone();
+            board_after_move.make_move(mv);
+            if leaves_king_in_check(&board_after_move) {
+                continue;
+            }
+            self.nodes += 1;
+
+            let score =
+                self.search_action(&mut board_after_move, depth, 0, alpha, beta, searched, 0);
+            searched += 1;
+
+            if self.stopped {
+                break;
+            }
+
+            if score > best_score || best_action.is_none() {
+                best_score = best_score.max(score);
+                best_action = Some(Action::from(mv));
+            }
+
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        if searched == 0 {
+            return (None, Self::no_legal_actions_score(&board, 0));
+        }
+
+        (best_action, best_score)
     }
 
-    fn recurse_moves(&mut self, board: &mut Board, for_white: bool, depth: i32) {
+    fn negamax(
+        &mut self,
+        board: &mut Board,
+        depth: i32,
+        ply: i32,
+        mut alpha: i32,
+        beta: i32,
+        allow_null: bool,
+    ) -> i32 {
+        if self.should_stop() {
+            return 0;
+        }
+
+        // Repeating a position reached since the root is enough to steer towards
+        // or away from, while earlier positions have to occur three times
+        let root = self.board.position_history.len() - 1;
+        if board.draw_reason().is_some() || board.repeats_since(root) {
+            // As in Board::status, mate takes precedence over the fifty-move rule
+            let is_mate = board.side_to_move_in_check()
+                && self.generator.generate_legal_actions(board).is_empty();
+            return match is_mate {
+                true => -MATE_SCORE + ply,
+                _ => 0,
+            };
+        }
+
+        if depth <= 0 || ply as usize >= MAX_PLY - 1 {
+            return evaluate(board);
+        }
+
+        let in_check = board.side_to_move_in_check();
+
+        if allow_null && !in_check && depth >= NULL_MOVE_MIN_DEPTH {
+            if let Some(score) = self.null_move_prune(board, depth, ply, beta) {
+                return score;
+            }
+        }
+
+        let mut picker = MovePicker::new(board, self.killers[ply as usize]);
+
+        let mut best_score = -MATE_SCORE;
+        let mut searched = 0;
+        while let Some(mv) = picker.next(&mut self.generator, board) {
+            let is_quiet = !mv.is_capture() && !mv.is_promotion();
+            let is_killer = self.killers[ply as usize].contains(&Some(mv));
+
+            let mut board_after_move = board.clone();
+            board_after_move.make_move(mv);
+            if leaves_king_in_check(&board_after_move) {
+                continue;
+            }
+            self.nodes += 1;
+
+            let gives_check = board_after_move.side_to_move_in_check();
+
+            let reduction = match depth >= LMR_MIN_DEPTH
+                && searched >= LMR_FULL_DEPTH_MOVES
+                && is_quiet
+                && !is_killer
+                && !in_check
+                && !gives_check
+            {
+                true => late_move_reduction(depth, searched),
+                _ => 0,
+            };
+
+            let score = self.search_action(
+                &mut board_after_move,
+                depth,
+                ply,
+                alpha,
+                beta,
+                searched,
+                reduction,
+            );
+            searched += 1;
+
+            if self.stopped {
+                return 0;
+            }
+
+            best_score = best_score.max(score);
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                if is_quiet {
+                    self.store_killer(mv, ply);
+                }
+                break;
+            }
+        }
+
+        if searched == 0 {
+            return Self::no_legal_actions_score(board, ply);
+        }
+
+        best_score
+    }
+
+    // Checkmate is scored by distance so that nearer mates are preferred
+    fn no_legal_actions_score(board: &Board, ply: i32) -> i32 {
+        match board.side_to_move_in_check() {
+            true => -MATE_SCORE + ply,
+            _ => 0,
+        }
+    }
+
+    // Principal variation search. Only the first move gets the full window; the
+    // rest are searched (possibly reduced) with a null window to prove they are
+    // worse, and re-searched if that proof fails.
+    #[allow(clippy::too_many_arguments)]
+    fn search_action(
+        &mut self,
+        board_after_move: &mut Board,
+        depth: i32,
+        ply: i32,
+        alpha: i32,
+        beta: i32,
+        index: usize,
+        reduction: i32,
+    ) -> i32 {
+        if index == 0 {
+            return -self.negamax(board_after_move, depth - 1, ply + 1, -beta, -alpha, true);
+        }
+
+        let mut score = -self.negamax(
+            board_after_move,
+            depth - 1 - reduction,
+            ply + 1,
+            -alpha - 1,
+            -alpha,
+            true,
+        );
+
+        if score > alpha && reduction > 0 {
+            score = -self.negamax(
+                board_after_move,
+                depth - 1,
+                ply + 1,
+                -alpha - 1,
+                -alpha,
+                true,
+            );
+        }
+
+        if score > alpha && score < beta {
+            score = -self.negamax(board_after_move, depth - 1, ply + 1, -beta, -alpha, true);
+        }
+
+        score
+    }
+
+    // Gives the opponent a free move. If a reduced search still fails high the
+    // position is good enough to cut without searching it fully.
+    fn null_move_prune(
+        &mut self,
+        board: &mut Board,
+        depth: i32,
+        ply: i32,
+        beta: i32,
+    ) -> Option<i32> {
+        // Passing is only safe when it can't be the best option, which rules out
+        // king and pawn endings prone to zugzwang
+        if !board.has_non_pawn_material(board.side_to_move) {
+            return None;
+        }
+
+        if evaluate(board) < beta {
+            return None;
+        }
+
+        let reduced_depth = depth - 1 - null_move_reduction(depth);
+
+        let undo = board.make_null_move();
+        let score = -self.negamax(board, reduced_depth, ply + 1, -beta, -beta + 1, false);
+        board.unmake_null_move(undo);
+
+        if self.stopped || score < beta {
+            return None;
+        }
+
+        if self.null_move_verification && depth >= NULL_MOVE_VERIFICATION_DEPTH {
+            let verified = self.negamax(board, reduced_depth, ply, beta - 1, beta, false);
+            if verified < beta {
+                return None;
+            }
+        }
+
+        // Mate scores found after passing aren't proven, so don't return them
+        Some(match score >= MATE_BOUND {
+            true => beta,
+            _ => score,
+        })
+    }
+
+    fn order_moves(&self, board: &Board, moves: &mut [Move], ply: i32, pv_move: Option<Move>) {
+        let killers = &self.killers[ply as usize];
+
+        moves.sort_by_cached_key(|&mv| {
+            let score = match pv_move == Some(mv) {
+                true => 1_000_000,
+                _ => order_score(board, mv, killers),
+            };
+
+            -score
+        });
+    }
+
+    fn store_killer(&mut self, mv: Move, ply: i32) {
+        let killers = &mut self.killers[ply as usize];
+        if killers[0] != Some(mv) {
+            killers[1] = killers[0].take();
+            killers[0] = Some(mv);
+        }
+    }
+
+    fn report(&mut self, info: SearchInfo) {
+        if let Some(callback) = self.info_callback.as_mut() {
+            callback(&info);
+        }
+    }
+
+    fn should_stop(&mut self) -> bool {
+        if self.stopped {
+            return true;
+        }
+
+        let over_node_limit = self.node_limit.is_some_and(|limit| self.nodes >= limit);
+        self.stopped = over_node_limit || self.signals.stop.load(Ordering::Relaxed);
+
+        if self.nodes % TIME_CHECK_INTERVAL == 0 {
+            match self.signals.ponder.load(Ordering::Relaxed) {
+                true => self.clock_started_at = Instant::now(),
+                _ => {
+                    self.stopped |= self
+                        .time_limit
+                        .is_some_and(|time_limit| self.clock_started_at.elapsed() >= time_limit)
+                }
+            }
+        }
+
+        self.stopped
+    }
+
+    fn recurse_moves(&mut self, board: &mut Board, color: Color, depth: i32) {
         if depth == self.max_depth {
-            // println!("Max Depth Reached {}", depth);
             return;
         }
 
         let valid_actions = self
             .generator
-            .generate_valid_actions(board.to_owned(), for_white);
+            .generate_valid_actions(board.to_owned(), color);
 
-        println!(
+        log!(
+            Trace,
             "Depth: {}  |  Valid actions {:?}  |  Nodes {}",
             depth + 1,
             valid_actions.len(),
@@ -50,11 +571,241 @@ impl Engine {
         for action in valid_actions {
             let mut board_after_move = board.clone();
             board_after_move.perform_action(action);
-            // println!("Moving for white? {}", for_white);
-            // board_after_move.print();
-            // println!(" ");
 
-            self.recurse_moves(&mut board_after_move, !for_white, depth + 1);
+            self.recurse_moves(&mut board_after_move, color.opposite(), depth + 1);
         }
     }
 }
+
+// Splits the remaining clock evenly over the moves left until the next time
+// control, plus most of the increment
+pub fn allocate_time(
+    remaining: Duration,
+    increment: Duration,
+    moves_to_go: Option<u32>,
+) -> Duration {
+    let moves_to_go = moves_to_go.unwrap_or(DEFAULT_MOVES_TO_GO).max(1);
+
+    let budget = remaining / moves_to_go + increment * 3 / 4;
+    budget.min(remaining.saturating_sub(MOVE_OVERHEAD))
+}
+
+fn leaves_king_in_check(board_after_move: &Board) -> bool {
+    board_after_move.in_check(board_after_move.side_to_move.opposite())
+}
+
+// Later moves in a well ordered list are less likely to be best, so they are
+// searched shallower. Always leaves at least one ply to search.
+fn late_move_reduction(depth: i32, index: usize) -> i32 {
+    let reduction = 0.75 + (depth as f64).ln() * ((index + 1) as f64).ln() / 2.25;
+    (reduction as i32).clamp(1, depth - 2)
+}
+
+fn null_move_reduction(depth: i32) -> i32 {
+    match depth > 6 {
+        true => 3,
+        _ => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::square::{A1, A2, A8, C6, D2, D5, E1, F1};
+    use std::{
+        sync::{atomic::Ordering, Arc, Mutex},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use super::{late_move_reduction, Engine, SearchInfo};
+    use crate::action::Action;
+    use crate::evaluation::{MATE_BOUND, MATE_SCORE};
+    use crate::moves::Move;
+
+    #[test]
+    fn search_captures_hanging_queen() {
+        let mut engine = Engine::new("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1");
+        engine.set_max_depth(3);
+
+        let result = engine.search();
+        let best_action = result.best_action.unwrap();
+
+        assert_eq!((best_action.from, best_action.to), (D2, D5));
+    }
+
+    #[test]
+    fn null_move_verification_finds_zugzwang_mate() {
+        // After Kc6 black's only moves are with the knight, each of which
+        // allows mate, but passing would be safe
+        let fen = "k7/2Q5/p2n4/P2Kp3/4P3/8/8/8 w - - 0 1";
+
+        let mut verified = Engine::new(fen);
+        verified.set_max_depth(7);
+        let mut unverified = Engine::new(fen);
+        unverified.set_max_depth(7);
+        unverified.set_null_move_verification(false);
+
+        let verified_result = verified.search();
+        let unverified_result = unverified.search();
+        let verified_action = verified_result.best_action.unwrap();
+
+        assert_eq!((verified_action.from, verified_action.to), (D5, C6));
+        assert!(verified_result.score >= MATE_BOUND);
+        assert!(unverified_result.score < MATE_BOUND);
+    }
+
+    #[test]
+    fn search_respects_time_limit() {
+        let mut engine =
+            Engine::new("r1bqkbnr/pppppppp/2n5/8/8/2N5/PPPPPPPP/R1BQKBNR w KQkq - 0 1");
+        engine.set_max_depth(64);
+        engine.set_time_limit(Some(Duration::from_millis(200)));
+
+        let result = engine.search();
+
+        assert!(result.best_action.is_some());
+        assert!(result.depth < 64);
+    }
+
+    #[test]
+    fn time_limit_starts_at_ponderhit() {
+        let mut engine =
+            Engine::new("r1bqkbnr/pppppppp/2n5/8/8/2N5/PPPPPPPP/R1BQKBNR w KQkq - 0 1");
+        engine.set_max_depth(64);
+        engine.set_time_limit(Some(Duration::from_millis(100)));
+        let signals = engine.signals();
+        signals.ponder.store(true, Ordering::Relaxed);
+
+        let search = thread::spawn(move || engine.search());
+        thread::sleep(Duration::from_millis(300));
+        let ponderhit = Instant::now();
+        signals.ponder.store(false, Ordering::Relaxed);
+        search.join().unwrap();
+
+        assert!(ponderhit.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn late_move_reduction_grows_with_depth_and_move_number() {
+        assert_eq!(late_move_reduction(3, 3), 1);
+        assert!(late_move_reduction(12, 30) > late_move_reduction(6, 30));
+        assert!(late_move_reduction(12, 30) > late_move_reduction(12, 4));
+        assert!(late_move_reduction(4, 60) <= 2);
+    }
+
+    #[test]
+    fn killers_are_ordered_before_quiet_moves() {
+        let mut engine = Engine::new("4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+        let killer = Move::new(A1, A8, Move::QUIET);
+        engine.store_killer(killer, 2);
+
+        let mut moves = [Move::new(A1, A2, Move::QUIET), killer];
+        let board = engine.board.clone();
+        engine.order_moves(&board, &mut moves, 2, None);
+
+        assert_eq!(moves[0], killer);
+    }
+
+    #[test]
+    fn info_callback_reports_each_iteration() {
+        let mut engine = Engine::new("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1");
+        engine.set_max_depth(5);
+
+        let depths = Arc::new(Mutex::new(Vec::new()));
+        let reported = Arc::clone(&depths);
+        engine.set_info_callback(Some(Box::new(move |info| {
+            if let SearchInfo::Iteration(result) = info {
+                reported.lock().unwrap().push(result.depth);
+            }
+        })));
+
+        let result = engine.search();
+
+        assert_eq!(*depths.lock().unwrap(), vec![1, 2, 3, 4, 5]);
+        assert_eq!(result.best_action.unwrap().to, D5);
+    }
+
+    #[test]
+    fn search_scores_mate_by_distance() {
+        let mut engine = Engine::new("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        engine.set_max_depth(3);
+
+        let result = engine.search();
+        let best_action = result.best_action.unwrap();
+
+        assert_eq!((best_action.from, best_action.to), (A1, A8));
+        assert_eq!(result.score, MATE_SCORE - 1);
+    }
+
+    #[test]
+    fn mate_takes_precedence_over_the_fifty_move_rule() {
+        let mut engine = Engine::new("6k1/5ppp/8/8/8/8/8/R5K1 w - - 99 80");
+        engine.set_max_depth(3);
+
+        let result = engine.search();
+        let best_action = result.best_action.unwrap();
+
+        assert_eq!((best_action.from, best_action.to), (A1, A8));
+        assert_eq!(result.score, MATE_SCORE - 1);
+    }
+
+    #[test]
+    fn search_scores_stalemate_as_draw() {
+        let mut engine = Engine::new("7k/5Q2/8/8/8/8/8/6K1 b - - 0 1");
+        engine.set_max_depth(2);
+
+        let result = engine.search();
+
+        assert!(result.best_action.is_none());
+        assert_eq!(result.score, 0);
+    }
+
+    #[test]
+    fn search_scores_insufficient_material_as_draw() {
+        let mut engine = Engine::new("4k3/8/8/8/8/8/3q4/3NK3 w - - 0 1");
+        engine.set_max_depth(3);
+
+        let result = engine.search();
+        let best_action = result.best_action.unwrap();
+
+        // Taking the queen leaves knight against bare king
+        assert_eq!((best_action.from, best_action.to), (E1, D2));
+        assert_eq!(result.score, 0);
+    }
+
+    #[test]
+    fn search_stops_at_node_limit() {
+        let mut engine =
+            Engine::new("r1bqkbnr/pppppppp/2n5/8/8/2N5/PPPPPPPP/R1BQKBNR w KQkq - 0 1");
+        engine.set_max_depth(64);
+        engine.set_node_limit(Some(5_000));
+
+        let result = engine.search();
+
+        assert!(result.best_action.is_some());
+        assert!(result.nodes < 6_000);
+    }
+
+    #[test]
+    fn search_only_considers_search_actions() {
+        let mut engine = Engine::new("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1");
+        engine.set_max_depth(3);
+        engine.set_search_actions(vec![Action::new(E1, F1, None)]);
+
+        let result = engine.search();
+
+        assert_eq!(result.best_action, Some(Action::new(E1, F1, None)));
+    }
+
+    #[test]
+    fn stop_signal_ends_the_search() {
+        let mut engine = Engine::new("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1");
+        engine.set_max_depth(64);
+        engine.signals().stop.store(true, Ordering::Relaxed);
+
+        let result = engine.search();
+
+        assert!(result.depth <= 1);
+        assert!(result.best_action.is_some());
+    }
+}
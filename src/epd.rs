@@ -0,0 +1,335 @@
+use std::{
+    error, fmt,
+    io::{self, BufRead, Write},
+    time::Duration,
+};
+
+use crate::{
+    action::{Action, MoveParseError},
+    board::Board,
+    engine::{Engine, MAX_PLY},
+};
+
+// A position as used by test suites: the first four FEN fields followed by
+// operations such as bm Qxf7+; id "WAC.001";
+#[derive(Debug, Clone)]
+pub struct Epd {
+    pub board: Board,
+    pub operations: Vec<(String, Vec<String>)>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EpdError {
+    InvalidFormat(String),
+    Move(MoveParseError),
+}
+
+impl fmt::Display for EpdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EpdError::InvalidFormat(message) => write!(f, "invalid EPD: {}", message),
+            EpdError::Move(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl error::Error for EpdError {}
+
+impl From<MoveParseError> for EpdError {
+    fn from(error: MoveParseError) -> Self {
+        EpdError::Move(error)
+    }
+}
+
+impl Epd {
+    pub fn parse(line: &str) -> Result<Epd, EpdError> {
+        let mut rest = line.trim();
+        let mut fields = Vec::new();
+        for _ in 0..4 {
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            fields.push(&rest[..end]);
+            rest = rest[end..].trim_start();
+        }
+        if fields.iter().any(|field| field.is_empty()) {
+            return Err(EpdError::InvalidFormat(format!(
+                "expected four position fields in {}",
+                line
+            )));
+        }
+
+        let operations = parse_operations(rest)?;
+        let operand = |opcode: &str, default: &str| {
+            operations
+                .iter()
+                .find(|(name, _)| name == opcode)
+                .and_then(|(_, operands)| operands.first().cloned())
+                .unwrap_or(default.to_string())
+        };
+        let fen = format!(
+            "{} {} {}",
+            fields.join(" "),
+            operand("hmvc", "0"),
+            operand("fmvn", "1")
+        );
+
+        let board = Board::from_fen(&fen)
+            .map_err(|error| EpdError::InvalidFormat(format!("{} in {}", error, line)))?;
+
+        Ok(Epd { board, operations })
+    }
+
+    pub fn operation(&self, opcode: &str) -> Option<&[String]> {
+        self.operations
+            .iter()
+            .find(|(name, _)| name == opcode)
+            .map(|(_, operands)| operands.as_slice())
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.operation("id")
+            .and_then(|operands| operands.first())
+            .map(String::as_str)
+    }
+
+    pub fn best_actions(&self) -> Result<Vec<Action>, EpdError> {
+        self.actions("bm")
+    }
+
+    pub fn avoid_actions(&self) -> Result<Vec<Action>, EpdError> {
+        self.actions("am")
+    }
+
+    // None when the position has neither a bm nor an am to check against
+    pub fn check(&self, action: &Action) -> Result<Option<bool>, EpdError> {
+        let best_actions = self.best_actions()?;
+        let avoid_actions = self.avoid_actions()?;
+        if best_actions.is_empty() && avoid_actions.is_empty() {
+            return Ok(None);
+        }
+
+        let is_best = best_actions.is_empty() || best_actions.contains(action);
+        let is_avoided = avoid_actions.contains(action);
+        Ok(Some(is_best && !is_avoided))
+    }
+
+    fn actions(&self, opcode: &str) -> Result<Vec<Action>, EpdError> {
+        self.operation(opcode)
+            .unwrap_or_default()
+            .iter()
+            .map(|san| self.board.parse_san(san).map_err(EpdError::from))
+            .collect()
+    }
+}
+
+fn parse_operations(text: &str) -> Result<Vec<(String, Vec<String>)>, EpdError> {
+    let mut operations = Vec::new();
+    let mut operation: Vec<String> = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        match char {
+            ';' => {
+                if !operation.is_empty() {
+                    let opcode = operation.remove(0);
+                    operations.push((opcode, std::mem::take(&mut operation)));
+                }
+            }
+            '"' => {
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(char) => string.push(char),
+                        None => {
+                            return Err(EpdError::InvalidFormat(format!(
+                                "unterminated string in {}",
+                                text
+                            )))
+                        }
+                    }
+                }
+                operation.push(string);
+            }
+            _ if char.is_whitespace() => {}
+            _ => {
+                let mut word = char.to_string();
+                while let Some(next) =
+                    chars.next_if(|next| !next.is_whitespace() && !matches!(next, ';' | '"'))
+                {
+                    word.push(next);
+                }
+                operation.push(word);
+            }
+        }
+    }
+
+    // Be lenient about a missing semicolon after the last operation
+    if !operation.is_empty() {
+        let opcode = operation.remove(0);
+        operations.push((opcode, operation));
+    }
+
+    Ok(operations)
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SuiteScore {
+    pub passed: usize,
+    pub total: usize,
+}
+
+// Searches every position for `movetime` and reports each result as it comes
+pub fn run_suite(
+    input: impl BufRead,
+    movetime: Duration,
+    output: &mut impl Write,
+) -> io::Result<SuiteScore> {
+    let mut score = SuiteScore::default();
+
+    for (index, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let name = format!("line {}", index + 1);
+        let epd = match Epd::parse(&line) {
+            Ok(epd) => epd,
+            Err(error) => {
+                writeln!(output, "{}: {}", name, error)?;
+                continue;
+            }
+        };
+        let name = epd.id().map_or(name, str::to_string);
+
+        let mut engine = Engine::from_board(epd.board.clone());
+        engine.set_max_depth(MAX_PLY as i32 - 1);
+        engine.set_time_limit(Some(movetime));
+        let Some(action) = engine.search().best_action else {
+            writeln!(output, "{}: skipped, no legal moves", name)?;
+            continue;
+        };
+        let played = epd.board.to_san(&action);
+
+        match epd.check(&action) {
+            Ok(Some(passed)) => {
+                score.total += 1;
+                if passed {
+                    score.passed += 1;
+                }
+                let verdict = match passed {
+                    true => "pass",
+                    _ => "fail",
+                };
+                writeln!(
+                    output,
+                    "{}: {}, played {}{}",
+                    name,
+                    verdict,
+                    played,
+                    expectation(&epd)
+                )?;
+            }
+            Ok(None) => writeln!(output, "{}: skipped, no bm or am", name)?,
+            // A bm or am that can't be played here can't be passed either
+            Err(error) => {
+                score.total += 1;
+                writeln!(output, "{}: fail, {}", name, error)?;
+            }
+        }
+    }
+
+    writeln!(output, "score {}/{}", score.passed, score.total)?;
+    Ok(score)
+}
+
+fn expectation(epd: &Epd) -> String {
+    ["bm", "am"]
+        .iter()
+        .filter_map(|opcode| {
+            epd.operation(opcode)
+                .map(|operands| format!(", {} {}", opcode, operands.join(" ")))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::square::{C4, E2, E3, E4, F3, F7};
+    use std::time::Duration;
+
+    use super::{run_suite, Epd, EpdError, SuiteScore};
+    use crate::action::{Action, MoveParseError};
+
+    #[test]
+    fn parses_position_and_operations() {
+        let epd = Epd::parse(
+            r#"r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5Q2/PPPP1PPP/RNB1K1NR w KQkq - bm Qxf7#; id "mate; in one"; c0 "scholar"; hmvc 3;"#,
+        )
+        .unwrap();
+
+        assert_eq!(epd.id(), Some("mate; in one"));
+        assert_eq!(epd.operation("c0"), Some(&["scholar".to_string()][..]));
+        assert_eq!(epd.board.halfmove_clock, 3);
+        assert_eq!(epd.best_actions().unwrap(), vec![Action::new(F3, F7, None)]);
+        assert_eq!(epd.check(&Action::new(F3, F7, None)), Ok(Some(true)));
+        assert_eq!(epd.check(&Action::new(C4, F7, None)), Ok(Some(false)));
+    }
+
+    #[test]
+    fn avoid_moves_fail_when_played() {
+        let epd = Epd::parse("4k3/8/8/8/8/8/4P3/4K3 w - - am e3").unwrap();
+
+        assert_eq!(epd.check(&Action::new(E2, E3, None)), Ok(Some(false)));
+        assert_eq!(epd.check(&Action::new(E2, E4, None)), Ok(Some(true)));
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert!(matches!(
+            Epd::parse("8/8/8 w"),
+            Err(EpdError::InvalidFormat(_))
+        ));
+        assert_eq!(
+            Epd::parse("kkkkkkkkk/8/8/8/8/8/8/K7 w - - bm Ka2;")
+                .unwrap_err()
+                .to_string(),
+            "invalid EPD: invalid piece placement kkkkkkkkk/8/8/8/8/8/8/K7 in \
+             kkkkkkkkk/8/8/8/8/8/8/K7 w - - bm Ka2;"
+        );
+        assert!(matches!(
+            Epd::parse("4k3/8/8/8/8/8/8/4K3 w - e9 bm Ke2;"),
+            Err(EpdError::InvalidFormat(_))
+        ));
+        assert_eq!(
+            Epd::parse("4k3/8/8/8/8/8/8/4K3 w - - bm Qh5")
+                .unwrap()
+                .best_actions(),
+            Err(EpdError::Move(MoveParseError::IllegalMove(
+                "Qh5".to_string()
+            )))
+        );
+    }
+
+    #[test]
+    fn run_suite_scores_each_position() {
+        let suite = "6k1/5ppp/8/8/8/8/8/R5K1 w - - bm Ra8#; id \"back rank\";\n\
+                     # comments and blank lines are skipped\n\n\
+                     4k3/8/8/8/8/8/8/4K3 w - - bm Qh5;\n";
+        let mut output = Vec::new();
+
+        let score = run_suite(suite.as_bytes(), Duration::from_millis(100), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert_eq!(
+            score,
+            SuiteScore {
+                passed: 1,
+                total: 2
+            }
+        );
+        assert!(output.contains("back rank: pass, played Ra8#, bm Ra8#"));
+        assert!(output.contains("line 4: fail, illegal move Qh5"));
+        assert!(output.ends_with("score 1/2\n"));
+    }
+}
@@ -0,0 +1,27 @@
+use crate::{
+    board::{Board, PieceIndices},
+    piece::Color,
+};
+
+pub const MATE_SCORE: i32 = 100_000;
+// Any score beyond this is a forced mate rather than a material balance
+pub const MATE_BOUND: i32 = MATE_SCORE - 1_000;
+
+// Material balance in centipawns from the side to move's point of view
+pub fn evaluate(board: &Board) -> i32 {
+    let material = |indices: &PieceIndices| -> i32 {
+        indices
+            .iter()
+            .filter_map(|&pos| board.piece_at(pos))
+            .filter(|piece| !piece.is_king())
+            .map(|piece| piece.get_value() * 100)
+            .sum()
+    };
+
+    let score = material(board.pieces(Color::White)) - material(board.pieces(Color::Black));
+
+    match board.side_to_move {
+        Color::White => score,
+        Color::Black => -score,
+    }
+}
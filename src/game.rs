@@ -0,0 +1,503 @@
+use std::time::Duration;
+
+use crate::{
+    action::{Action, MoveParseError},
+    board::{Board, DrawReason, GameStatus},
+    piece::Color,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    Unknown,
+}
+
+impl GameResult {
+    pub fn from_pgn(text: &str) -> Option<GameResult> {
+        match text {
+            "1-0" => Some(GameResult::WhiteWins),
+            "0-1" => Some(GameResult::BlackWins),
+            "1/2-1/2" => Some(GameResult::Draw),
+            "*" => Some(GameResult::Unknown),
+            _ => None,
+        }
+    }
+
+    pub fn as_pgn(&self) -> &'static str {
+        match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+            GameResult::Unknown => "*",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Termination {
+    Checkmate,
+    Stalemate,
+    Draw(DrawReason),
+    Resignation,
+    Agreement,
+    TimeForfeit,
+    // Decided by an arbiter or test harness rather than on the board
+    Adjudication,
+}
+
+impl Termination {
+    // The value of the PGN Termination tag
+    pub fn as_pgn(&self) -> &'static str {
+        match self {
+            Termination::TimeForfeit => "time forfeit",
+            Termination::Adjudication => "adjudication",
+            _ => "normal",
+        }
+    }
+}
+
+// Remaining time for each side, indexed by Color::index, and the increment
+// added after every move
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Clock {
+    pub remaining: [Duration; 2],
+    pub increment: Duration,
+}
+
+impl Clock {
+    pub fn new(base: Duration, increment: Duration) -> Clock {
+        Clock {
+            remaining: [base; 2],
+            increment,
+        }
+    }
+
+    pub fn remaining(&self, color: Color) -> Duration {
+        self.remaining[color.index()]
+    }
+}
+
+// Commentary on a move, as read from or written to PGN
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Annotation {
+    pub nags: Vec<u8>,
+    // Comments before the first move of a line are kept on that move
+    pub comment_before: Option<String>,
+    pub comment: Option<String>,
+    // Engine analysis, with the score from the point of view of the mover
+    pub score: Option<i32>,
+    pub depth: Option<u32>,
+    // Alternatives to this move, each starting from the position before it
+    pub variations: Vec<Vec<GameMove>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameMove {
+    pub action: Action,
+    // Thinking time, when the game is played on a clock
+    pub elapsed: Option<Duration>,
+    pub annotation: Annotation,
+    // The mover's clock before the move, put back when it is taken back
+    remaining_before: Option<Duration>,
+}
+
+impl GameMove {
+    pub fn new(action: Action) -> GameMove {
+        GameMove {
+            action,
+            elapsed: None,
+            annotation: Annotation::default(),
+            remaining_before: None,
+        }
+    }
+
+    pub fn line(actions: Vec<Action>) -> Vec<GameMove> {
+        actions.into_iter().map(GameMove::new).collect()
+    }
+}
+
+// A game from its starting position: the moves played since, how it ended and
+// its PGN tags. Taken back moves are kept for redo until a different move is
+// played.
+#[derive(Debug, Clone)]
+pub struct Game {
+    start: Board,
+    board: Board,
+    moves: Vec<GameMove>,
+    undone: Vec<GameMove>,
+    result: GameResult,
+    termination: Option<Termination>,
+    tags: Vec<(String, String)>,
+    clock: Option<Clock>,
+}
+
+impl Game {
+    pub fn new(start: Board) -> Game {
+        Game::from_record(start, Vec::new(), Vec::new(), GameResult::Unknown)
+    }
+
+    // A finished record such as a PGN game, whose moves are already known to be
+    // legal. The board only decides the result when the record doesn't.
+    pub(crate) fn from_record(
+        start: Board,
+        tags: Vec<(String, String)>,
+        moves: Vec<GameMove>,
+        result: GameResult,
+    ) -> Game {
+        let mut board = start.clone();
+        for game_move in &moves {
+            board.perform_action(game_move.action.clone());
+        }
+
+        let mut game = Game {
+            start,
+            board,
+            moves,
+            undone: Vec::new(),
+            result: GameResult::Unknown,
+            termination: None,
+            tags,
+            clock: None,
+        };
+        game.update_result();
+        if result != GameResult::Unknown && result != game.result {
+            game.result = result;
+            game.termination = None;
+        }
+        game
+    }
+
+    pub fn start(&self) -> &Board {
+        &self.start
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn moves(&self) -> &[GameMove] {
+        &self.moves
+    }
+
+    pub fn actions(&self) -> impl Iterator<Item = &Action> {
+        self.moves.iter().map(|game_move| &game_move.action)
+    }
+
+    // Counting plies from 0
+    pub fn annotation_mut(&mut self, ply: usize) -> Option<&mut Annotation> {
+        self.moves
+            .get_mut(ply)
+            .map(|game_move| &mut game_move.annotation)
+    }
+
+    // The starting position followed by the position after each move
+    pub fn positions(&self) -> Vec<Board> {
+        let mut board = self.start.clone();
+        let mut positions = vec![board.clone()];
+
+        for game_move in &self.moves {
+            board.perform_action(game_move.action.clone());
+            positions.push(board.clone());
+        }
+
+        positions
+    }
+
+    pub fn result(&self) -> GameResult {
+        self.result
+    }
+
+    pub fn termination(&self) -> Option<Termination> {
+        self.termination
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.result != GameResult::Unknown
+    }
+
+    pub fn clock(&self) -> Option<&Clock> {
+        self.clock.as_ref()
+    }
+
+    pub fn set_clock(&mut self, clock: Option<Clock>) {
+        self.clock = clock;
+    }
+
+    pub fn set_tag(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, existing)) => *existing = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn tags(&self) -> &[(String, String)] {
+        &self.tags
+    }
+
+    // The action has to be legal in the current position
+    pub fn play(&mut self, action: Action) {
+        self.push(GameMove::new(action));
+    }
+
+    // Charges the mover's clock, which loses on time once it runs out
+    pub fn play_timed(&mut self, action: Action, elapsed: Duration) {
+        self.push(GameMove {
+            elapsed: Some(elapsed),
+            ..GameMove::new(action)
+        });
+    }
+
+    pub fn play_uci(&mut self, text: &str) -> Result<(), MoveParseError> {
+        let action = self.board.parse_uci_move(text)?;
+        self.play(action);
+        Ok(())
+    }
+
+    // Takes back up to `count` moves, returning how many were taken back
+    pub fn undo(&mut self, count: usize) -> usize {
+        let count = count.min(self.moves.len());
+        if count == 0 {
+            return 0;
+        }
+        // Latest first, so each side ends up with its time from before the
+        // earliest move taken back
+        let mut mover = self.board.side_to_move.opposite();
+        for _ in 0..count {
+            let Some(game_move) = self.moves.pop() else {
+                break;
+            };
+            if let (Some(clock), Some(remaining)) =
+                (self.clock.as_mut(), game_move.remaining_before)
+            {
+                clock.remaining[mover.index()] = remaining;
+            }
+            self.undone.push(game_move);
+            mover = mover.opposite();
+        }
+
+        // There is no unmake for actions, so the position is replayed
+        self.board = self.start.clone();
+        for game_move in &self.moves {
+            self.board.perform_action(game_move.action.clone());
+        }
+
+        self.result = GameResult::Unknown;
+        self.termination = None;
+        self.update_result();
+        count
+    }
+
+    // Replays up to `count` taken back moves, returning how many were replayed
+    pub fn redo(&mut self, count: usize) -> usize {
+        let mut replayed = 0;
+        while replayed < count {
+            let Some(game_move) = self.undone.pop() else {
+                break;
+            };
+            self.apply(game_move);
+            replayed += 1;
+        }
+        replayed
+    }
+
+    pub fn resign(&mut self, color: Color) {
+        self.finish(winner(color.opposite()), Termination::Resignation);
+    }
+
+    pub fn agree_draw(&mut self) {
+        self.finish(GameResult::Draw, Termination::Agreement);
+    }
+
+    pub fn finish(&mut self, result: GameResult, termination: Termination) {
+        self.result = result;
+        self.termination = Some(termination);
+    }
+
+    fn push(&mut self, game_move: GameMove) {
+        self.undone.clear();
+        self.apply(game_move);
+    }
+
+    fn apply(&mut self, mut game_move: GameMove) {
+        let mover = self.board.side_to_move;
+        self.board.perform_action(game_move.action.clone());
+
+        let mut flagged = false;
+        if let (Some(clock), Some(elapsed)) = (self.clock.as_mut(), game_move.elapsed) {
+            let remaining = &mut clock.remaining[mover.index()];
+            game_move.remaining_before = Some(*remaining);
+            flagged = elapsed > *remaining;
+            *remaining = remaining.saturating_sub(elapsed) + clock.increment;
+        }
+        self.moves.push(game_move);
+
+        match flagged {
+            true => self.finish(winner(mover.opposite()), Termination::TimeForfeit),
+            _ => self.update_result(),
+        }
+    }
+
+    fn update_result(&mut self) {
+        match self.board.status() {
+            GameStatus::Ongoing => {}
+            GameStatus::Checkmate(color) => self.finish(winner(color), Termination::Checkmate),
+            GameStatus::Stalemate => self.finish(GameResult::Draw, Termination::Stalemate),
+            GameStatus::Draw(reason) => self.finish(GameResult::Draw, Termination::Draw(reason)),
+        }
+    }
+}
+
+fn winner(color: Color) -> GameResult {
+    match color {
+        Color::White => GameResult::WhiteWins,
+        Color::Black => GameResult::BlackWins,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Clock, Game, GameResult, Termination};
+    use crate::{
+        board::{Board, DrawReason, DEFAULT_FEN},
+        pgn,
+        piece::Color,
+    };
+
+    fn play_all(game: &mut Game, moves: &[&str]) {
+        for text in moves {
+            game.play_uci(text).unwrap();
+        }
+    }
+
+    #[test]
+    fn undo_and_redo_walk_the_history() {
+        let mut game = Game::new(Board::new(DEFAULT_FEN));
+        play_all(&mut game, &["e2e4", "e7e5", "g1f3"]);
+        let after_three = game.board().to_fen();
+
+        assert_eq!(game.undo(2), 2);
+        assert_eq!(game.moves().len(), 1);
+        assert_eq!(game.board().side_to_move, Color::Black);
+
+        assert_eq!(game.redo(5), 2);
+        assert_eq!(game.board().to_fen(), after_three);
+
+        game.undo(1);
+        game.play_uci("f1c4").unwrap();
+        assert_eq!(game.redo(1), 0);
+        assert_eq!(game.undo(10), 3);
+        assert_eq!(game.board().to_fen(), DEFAULT_FEN);
+    }
+
+    #[test]
+    fn records_how_the_game_ended() {
+        let mut game = Game::new(Board::new(DEFAULT_FEN));
+        play_all(&mut game, &["f2f3", "e7e5", "g2g4", "d8h4"]);
+
+        assert_eq!(game.result(), GameResult::BlackWins);
+        assert_eq!(game.termination(), Some(Termination::Checkmate));
+
+        game.undo(1);
+        assert!(!game.is_over());
+        game.resign(Color::Black);
+        assert_eq!(game.result(), GameResult::WhiteWins);
+
+        let mut game = Game::new(Board::new(DEFAULT_FEN));
+        play_all(
+            &mut game,
+            &[
+                "g1f3", "g8f6", "f3g1", "f6g8", "g1f3", "g8f6", "f3g1", "f6g8",
+            ],
+        );
+        assert_eq!(
+            game.termination(),
+            Some(Termination::Draw(DrawReason::ThreefoldRepetition))
+        );
+    }
+
+    #[test]
+    fn clocks_run_down_and_flag() {
+        let mut game = Game::new(Board::new(DEFAULT_FEN));
+        game.set_clock(Some(Clock::new(
+            Duration::from_secs(10),
+            Duration::from_secs(1),
+        )));
+        let e4 = game.board().parse_uci_move("e2e4").unwrap();
+        game.play_timed(e4, Duration::from_secs(4));
+
+        let clock = game.clock().unwrap();
+        assert_eq!(clock.remaining(Color::White), Duration::from_secs(7));
+        assert_eq!(clock.remaining(Color::Black), Duration::from_secs(10));
+
+        game.undo(1);
+        assert_eq!(
+            game.clock().unwrap().remaining(Color::White),
+            Duration::from_secs(10)
+        );
+
+        let e4 = game.board().parse_uci_move("e2e4").unwrap();
+        game.play_timed(e4, Duration::from_secs(11));
+        assert_eq!(game.result(), GameResult::BlackWins);
+        assert_eq!(game.termination(), Some(Termination::TimeForfeit));
+
+        // Taking back the move that flagged puts back exactly the time before it
+        game.undo(1);
+        assert!(!game.is_over());
+        assert_eq!(
+            game.clock().unwrap().remaining(Color::White),
+            Duration::from_secs(10)
+        );
+    }
+
+    #[test]
+    fn undoing_several_timed_moves_restores_both_clocks() {
+        let mut game = Game::new(Board::new(DEFAULT_FEN));
+        game.set_clock(Some(Clock::new(
+            Duration::from_secs(60),
+            Duration::from_secs(2),
+        )));
+        for (text, seconds) in [("e2e4", 5), ("e7e5", 7), ("g1f3", 3)] {
+            let action = game.board().parse_uci_move(text).unwrap();
+            game.play_timed(action, Duration::from_secs(seconds));
+        }
+
+        game.undo(3);
+        let clock = game.clock().unwrap();
+        assert_eq!(clock.remaining(Color::White), Duration::from_secs(60));
+        assert_eq!(clock.remaining(Color::Black), Duration::from_secs(60));
+
+        game.redo(3);
+        let clock = game.clock().unwrap();
+        assert_eq!(clock.remaining(Color::White), Duration::from_secs(56));
+        assert_eq!(clock.remaining(Color::Black), Duration::from_secs(55));
+    }
+
+    #[test]
+    fn round_trips_through_pgn() {
+        let mut game = Game::new(Board::new(DEFAULT_FEN));
+        game.set_tag("White", "Krabs");
+        play_all(&mut game, &["e2e4", "e7e5"]);
+        game.agree_draw();
+
+        let text = game.to_pgn();
+        assert!(text.contains("[White \"Krabs\"]"));
+        assert!(text.contains("[Termination \"normal\"]"));
+        assert!(text.contains("1. e4 e5 1/2-1/2"));
+
+        let game = &pgn::read_games(&text).unwrap()[0];
+        assert_eq!(game.moves().len(), 2);
+        assert_eq!(game.result(), GameResult::Draw);
+        assert_eq!(game.tag("White"), Some("Krabs"));
+    }
+}
@@ -3,5 +3,21 @@ pub mod action_generator;
 pub mod board;
 pub mod directions;
 pub mod engine;
+pub mod epd;
+pub mod evaluation;
+pub mod game;
+pub mod logger;
+pub mod move_picker;
+pub mod moves;
+pub mod pgn;
 pub mod piece;
 pub mod precomputed_data;
+pub mod render;
+pub mod san;
+#[cfg(feature = "serde")]
+mod serialization;
+pub mod square;
+pub mod square_set;
+pub mod svg;
+pub mod uci;
+pub mod xboard;
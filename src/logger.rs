@@ -0,0 +1,170 @@
+use std::{
+    fmt,
+    io::{stderr, Write},
+    str::FromStr,
+    sync::RwLock,
+};
+
+// Diagnostics from library code go through here rather than stdout, which the
+// UCI and XBoard front ends use to talk to the GUI. Nothing is logged until a
+// logger is installed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Level {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.to_ascii_lowercase().as_str() {
+            "error" => Ok(Level::Error),
+            "warn" => Ok(Level::Warn),
+            "info" => Ok(Level::Info),
+            "debug" => Ok(Level::Debug),
+            "trace" => Ok(Level::Trace),
+            _ => Err(format!("unknown log level {}", text)),
+        }
+    }
+}
+
+pub trait Logger: Send + Sync {
+    fn enabled(&self, level: Level) -> bool;
+    fn log(&self, level: Level, message: fmt::Arguments);
+}
+
+static LOGGER: RwLock<Option<Box<dyn Logger>>> = RwLock::new(None);
+
+pub fn set_logger(logger: impl Logger + 'static) {
+    *LOGGER.write().unwrap_or_else(|error| error.into_inner()) = Some(Box::new(logger));
+}
+
+pub fn clear_logger() {
+    *LOGGER.write().unwrap_or_else(|error| error.into_inner()) = None;
+}
+
+pub fn enabled(level: Level) -> bool {
+    let logger = LOGGER.read().unwrap_or_else(|error| error.into_inner());
+    logger.as_ref().is_some_and(|logger| logger.enabled(level))
+}
+
+// Use the log! macro instead, which only evaluates and formats the arguments
+// when the message is wanted
+pub fn log(level: Level, message: fmt::Arguments) {
+    let logger = LOGGER.read().unwrap_or_else(|error| error.into_inner());
+    if let Some(logger) = logger.as_ref().filter(|logger| logger.enabled(level)) {
+        logger.log(level, message);
+    }
+}
+
+// log!(Debug, "searched {} nodes", nodes)
+#[macro_export]
+macro_rules! log {
+    ($level:ident, $($arg:tt)+) => {
+        if $crate::logger::enabled($crate::logger::Level::$level) {
+            $crate::logger::log($crate::logger::Level::$level, format_args!($($arg)+))
+        }
+    };
+}
+
+// Writes everything at `level` or more severe to stderr
+#[derive(Debug, Clone, Copy)]
+pub struct StderrLogger {
+    pub level: Level,
+}
+
+impl Logger for StderrLogger {
+    fn enabled(&self, level: Level) -> bool {
+        level <= self.level
+    }
+
+    fn log(&self, level: Level, message: fmt::Arguments) {
+        let _ = writeln!(stderr(), "[{}] {}", level, message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fmt,
+        sync::{Arc, Mutex},
+    };
+
+    use super::{clear_logger, set_logger, Level, Logger};
+    use crate::board::{Board, DEFAULT_FEN};
+
+    struct Collector {
+        level: Level,
+        messages: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Logger for Collector {
+        fn enabled(&self, level: Level) -> bool {
+            level <= self.level
+        }
+
+        fn log(&self, level: Level, message: fmt::Arguments) {
+            let mut messages = self.messages.lock().unwrap();
+            messages.push(format!("{} {}", level, message));
+        }
+    }
+
+    #[test]
+    fn routes_messages_to_the_installed_logger_by_level() {
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        set_logger(Collector {
+            level: Level::Info,
+            messages: Arc::clone(&messages),
+        });
+
+        crate::log!(Warn, "{} pieces", 32);
+        crate::log!(Trace, "too detailed");
+        Board::new(DEFAULT_FEN);
+        clear_logger();
+        crate::log!(Error, "nobody listening");
+
+        let messages = messages.lock().unwrap();
+        assert!(messages.contains(&"warn 32 pieces".to_string()));
+        assert!(!messages.iter().any(|message| {
+            message.contains("too detailed")
+                || message.contains("nobody listening")
+                || message.starts_with("debug")
+        }));
+    }
+
+    #[test]
+    fn arguments_are_not_evaluated_for_disabled_levels() {
+        let mut evaluated = false;
+        // No test installs a logger that wants Trace
+        crate::log!(Trace, "{}", {
+            evaluated = true;
+            Board::new(DEFAULT_FEN).to_fen()
+        });
+
+        assert!(!evaluated);
+    }
+
+    #[test]
+    fn parses_level_names() {
+        assert_eq!("DEBUG".parse(), Ok(Level::Debug));
+        assert!("loud".parse::<Level>().is_err());
+        assert!(Level::Error < Level::Trace);
+    }
+}
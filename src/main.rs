@@ -1,9 +1,81 @@
-use rust_krabs::engine::Engine;
+use std::{
+    env,
+    fs::File,
+    io::{stdin, stdout, BufRead, BufReader, Cursor, Read},
+    process,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
-const DEFAULT_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+use rust_krabs::{
+    epd,
+    logger::{self, StderrLogger},
+    uci, xboard,
+};
+
+const DEFAULT_EPD_MOVETIME: u64 = 1000;
+// Set to error, warn, info, debug or trace to log to stderr
+const LOG_LEVEL_VAR: &str = "RUST_KRABS_LOG";
 
 fn main() {
-    let mut engine = Engine::new(DEFAULT_FEN);
+    if let Some(level) = env::var(LOG_LEVEL_VAR)
+        .ok()
+        .and_then(|level| level.parse().ok())
+    {
+        logger::set_logger(StderrLogger { level });
+    }
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("epd") {
+        run_epd(&args[1..]);
+        return;
+    }
+
+    let mut input = stdin().lock();
+    let output = Arc::new(Mutex::new(stdout()));
+
+    // The first command tells us which protocol the GUI speaks
+    let mut first_line = String::new();
+    if input.read_line(&mut first_line).is_err() {
+        return;
+    }
+    let input = Cursor::new(first_line.clone()).chain(input);
+
+    match first_line.trim() {
+        "xboard" => xboard::run(input, output),
+        _ => uci::run(input, output),
+    }
+}
+
+// rust-krabs epd <file> [--movetime <milliseconds>]
+fn run_epd(args: &[String]) {
+    let Some(path) = args.first() else {
+        eprintln!("usage: rust-krabs epd <file> [--movetime <milliseconds>]");
+        process::exit(2);
+    };
+
+    let movetime = match args.iter().position(|arg| arg == "--movetime") {
+        Some(index) => match args.get(index + 1).and_then(|value| value.parse().ok()) {
+            Some(movetime) => movetime,
+            None => {
+                eprintln!("--movetime expects a number of milliseconds");
+                process::exit(2);
+            }
+        },
+        None => DEFAULT_EPD_MOVETIME,
+    };
+
+    let file = File::open(path).unwrap_or_else(|error| {
+        eprintln!("cannot open {}: {}", path, error);
+        process::exit(1);
+    });
 
-    engine.evaluate();
+    if let Err(error) = epd::run_suite(
+        BufReader::new(file),
+        Duration::from_millis(movetime),
+        &mut stdout(),
+    ) {
+        eprintln!("cannot read {}: {}", path, error);
+        process::exit(1);
+    }
 }
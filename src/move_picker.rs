@@ -0,0 +1,190 @@
+use crate::{
+    action_generator::{ActionGenerator, GenerationStage},
+    board::Board,
+    moves::{Move, MoveList},
+    piece::PieceType,
+};
+
+// Hands out moves one stage at a time, best first within each stage: captures
+// and promotions, then quiet moves with the killers leading. In check it
+// generates evasions only. Nothing is generated until the previous stage has
+// been used up, so a cutoff skips the remaining work.
+pub struct MovePicker {
+    next_stage: Option<GenerationStage>,
+    killers: [Option<Move>; 2],
+    moves: MoveList,
+    index: usize,
+}
+
+impl MovePicker {
+    pub fn new(board: &Board, killers: [Option<Move>; 2]) -> MovePicker {
+        let first_stage = match board.checkers().is_empty() {
+            true => GenerationStage::Captures,
+            _ => GenerationStage::Evasions,
+        };
+
+        MovePicker {
+            next_stage: Some(first_stage),
+            killers,
+            moves: MoveList::new(),
+            index: 0,
+        }
+    }
+
+    // Pseudo-legal moves, so the caller still has to reject those that leave
+    // the king in check
+    pub fn next(&mut self, generator: &mut ActionGenerator, board: &Board) -> Option<Move> {
+        while self.index == self.moves.len() {
+            let stage = self.next_stage?;
+            self.next_stage = match stage {
+                GenerationStage::Captures => Some(GenerationStage::Quiets),
+                _ => None,
+            };
+
+            self.moves = generator.generate_stage(board, board.side_to_move, stage);
+            self.moves
+                .sort_by_cached_key(|&mv| -order_score(board, mv, &self.killers));
+            self.index = 0;
+        }
+
+        self.index += 1;
+        Some(self.moves[self.index - 1])
+    }
+}
+
+// Higher is searched earlier
+pub fn order_score(board: &Board, mv: Move, killers: &[Option<Move>; 2]) -> i32 {
+    // En passant is the one capture that lands on an empty square
+    let victim = match mv.is_capture() {
+        true => board
+            .piece_at(mv.to())
+            .map_or(PieceType::Pawn.value(), |victim| victim.get_value()),
+        _ => 0,
+    };
+    // Promoting wins material just as a capture does
+    let promotion = mv
+        .promotion_piece()
+        .map_or(0, |piece_type| piece_type.value());
+
+    if victim + promotion > 0 {
+        let attacker = board
+            .piece_at(mv.from())
+            .map_or(0, |attacker| attacker.get_value());
+        // Most valuable victim, least valuable attacker
+        100_000 + (victim + promotion) * 10 - attacker
+    } else if killers[0] == Some(mv) {
+        90_000
+    } else if killers[1] == Some(mv) {
+        80_000
+    } else {
+        0
+    }
+}
+
+// The picker as a plain iterator, for callers that don't need the generator
+// between moves
+pub struct StagedMoves<'a> {
+    generator: &'a mut ActionGenerator,
+    board: &'a Board,
+    picker: MovePicker,
+}
+
+impl<'a> StagedMoves<'a> {
+    pub fn new(generator: &'a mut ActionGenerator, board: &'a Board) -> StagedMoves<'a> {
+        StagedMoves {
+            picker: MovePicker::new(board, [None, None]),
+            generator,
+            board,
+        }
+    }
+}
+
+impl Iterator for StagedMoves<'_> {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Move> {
+        self.picker.next(self.generator, self.board)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{order_score, MovePicker, StagedMoves};
+    use crate::square::{B1, B7, B8, C3, D3, D5, D6, E1, E2, E3, E4, E5, F1};
+    use crate::{
+        action_generator::{ActionGenerator, GenerationStage},
+        board::{Board, DEFAULT_FEN},
+        moves::Move,
+        piece::PieceType,
+    };
+
+    #[test]
+    fn yields_every_move_with_captures_first() {
+        let mut generator = ActionGenerator::default();
+        let board = Board::new("4k3/8/8/3p4/4P3/8/8/R3K2R w KQ - 0 1");
+
+        let mut staged: Vec<Move> = StagedMoves::new(&mut generator, &board).collect();
+        let mut all = generator
+            .generate_moves(&board, board.side_to_move)
+            .to_vec();
+
+        assert_eq!(staged[0], Move::new(E4, D5, Move::CAPTURE));
+        assert!(staged[1..].iter().all(|mv| !mv.is_capture()));
+        staged.sort_by_key(|mv| (mv.from(), mv.to()));
+        all.sort_by_key(|mv| (mv.from(), mv.to()));
+        assert_eq!(staged, all);
+    }
+
+    #[test]
+    fn quiets_are_only_generated_once_captures_run_out() {
+        let mut generator = ActionGenerator::default();
+        let board = Board::new("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1");
+        let mut picker = MovePicker::new(&board, [None, None]);
+
+        assert!(picker.next(&mut generator, &board).unwrap().is_capture());
+        assert_eq!(picker.next_stage, Some(GenerationStage::Quiets));
+        assert!(!picker.next(&mut generator, &board).unwrap().is_capture());
+        assert_eq!(picker.next_stage, None);
+    }
+
+    #[test]
+    fn killers_lead_the_quiet_moves() {
+        let mut generator = ActionGenerator::default();
+        let board = Board::new(DEFAULT_FEN);
+        let killer = Move::new(B1, C3, Move::QUIET);
+        let mut picker = MovePicker::new(&board, [None, Some(killer)]);
+
+        assert_eq!(picker.next(&mut generator, &board), Some(killer));
+    }
+
+    #[test]
+    fn en_passant_and_promotions_are_ordered_with_captures() {
+        let board = Board::new("4k3/1P6/8/3pP3/8/8/8/4K3 w - d6 0 1");
+        let killer = Move::new(E1, E2, Move::QUIET);
+        let killers = [Some(killer), None];
+        let score = |mv| order_score(&board, mv, &killers);
+
+        let en_passant = Move::new(E5, D6, Move::EN_PASSANT);
+        let queen = Move::promotion(B7, B8, PieceType::Queen, false);
+        let knight = Move::promotion(B7, B8, PieceType::Knight, false);
+
+        assert!(score(en_passant) > score(killer));
+        assert!(score(knight) > score(killer));
+        assert!(score(queen) > score(knight));
+        assert!(score(queen) > score(en_passant));
+    }
+
+    #[test]
+    fn only_evasions_are_generated_in_check() {
+        let mut generator = ActionGenerator::default();
+        let board = Board::new("4k3/8/8/4r3/8/3N4/8/4KB2 w - - 0 1");
+
+        let evasions: Vec<Move> = StagedMoves::new(&mut generator, &board).collect();
+
+        assert_eq!(evasions[0], Move::new(D3, E5, Move::CAPTURE));
+        assert!(evasions.contains(&Move::new(F1, E2, Move::QUIET)));
+        assert!(evasions
+            .iter()
+            .all(|mv| mv.from() == E1 || [E2, E3, E4, E5].contains(&mv.to())));
+    }
+}
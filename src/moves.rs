@@ -0,0 +1,266 @@
+use std::ops::{Deref, DerefMut};
+
+use crate::{
+    action::{Action, ActionType},
+    board::Board,
+    piece::PieceType,
+    square::Square,
+};
+
+// No position has more legal moves than this, with room to spare for
+// pseudo-legal ones
+pub const MAX_MOVES: usize = 256;
+
+// A move packed into 16 bits: the origin in bits 0-5, the destination in bits
+// 6-11 and the flags in bits 12-15
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Move(u16);
+
+impl Move {
+    pub const QUIET: u16 = 0;
+    pub const DOUBLE_PUSH: u16 = 1;
+    pub const KING_CASTLE: u16 = 2;
+    pub const QUEEN_CASTLE: u16 = 3;
+    pub const CAPTURE: u16 = 4;
+    pub const EN_PASSANT: u16 = 5;
+    // The low two bits pick the piece, and captures add CAPTURE on top
+    pub const KNIGHT_PROMOTION: u16 = 8;
+    pub const BISHOP_PROMOTION: u16 = 9;
+    pub const ROOK_PROMOTION: u16 = 10;
+    pub const QUEEN_PROMOTION: u16 = 11;
+
+    const PROMOTION: u16 = 8;
+
+    pub const fn new(from: Square, to: Square, flags: u16) -> Move {
+        Move(from.index() as u16 | (to.index() as u16) << 6 | (flags & 0xf) << 12)
+    }
+
+    pub fn promotion(from: Square, to: Square, piece_type: PieceType, is_capture: bool) -> Move {
+        let piece = match piece_type {
+            PieceType::Bishop => Move::BISHOP_PROMOTION,
+            PieceType::Rook => Move::ROOK_PROMOTION,
+            PieceType::Queen => Move::QUEEN_PROMOTION,
+            _ => Move::KNIGHT_PROMOTION,
+        };
+        let capture = match is_capture {
+            true => Move::CAPTURE,
+            _ => 0,
+        };
+
+        Move::new(from, to, piece | capture)
+    }
+
+    // The flags can't be recovered from an Action alone, so the position it is
+    // played in fills them in
+    pub fn from_action(action: &Action, board: &Board) -> Move {
+        let is_capture = board.piece_at(action.to).is_some();
+        let is_double_push = board
+            .piece_at(action.from)
+            .is_some_and(|piece| piece.piece_type.eq(&PieceType::Pawn))
+            && action.to.rank().abs_diff(action.from.rank()) == 2;
+
+        let flags = match action.action_type {
+            ActionType::Promote(piece_type) => {
+                return Move::promotion(action.from, action.to, piece_type, is_capture)
+            }
+            ActionType::Castle => match action.to > action.from {
+                true => Move::KING_CASTLE,
+                _ => Move::QUEEN_CASTLE,
+            },
+            ActionType::EnPassant => Move::EN_PASSANT,
+            ActionType::Normal if is_capture => Move::CAPTURE,
+            ActionType::Normal if is_double_push => Move::DOUBLE_PUSH,
+            ActionType::Normal => Move::QUIET,
+        };
+
+        Move::new(action.from, action.to, flags)
+    }
+
+    pub const fn from(self) -> Square {
+        Square::from_bits(self.0)
+    }
+
+    pub const fn to(self) -> Square {
+        Square::from_bits(self.0 >> 6)
+    }
+
+    pub const fn flags(self) -> u16 {
+        self.0 >> 12
+    }
+
+    pub const fn is_capture(self) -> bool {
+        self.flags() & Move::CAPTURE != 0
+    }
+
+    pub const fn is_castle(self) -> bool {
+        matches!(self.flags(), Move::KING_CASTLE | Move::QUEEN_CASTLE)
+    }
+
+    pub const fn is_promotion(self) -> bool {
+        self.flags() & Move::PROMOTION != 0
+    }
+
+    pub fn promotion_piece(self) -> Option<PieceType> {
+        if !self.is_promotion() {
+            return None;
+        }
+
+        Some(match self.flags() & 3 {
+            0 => PieceType::Knight,
+            1 => PieceType::Bishop,
+            2 => PieceType::Rook,
+            _ => PieceType::Queen,
+        })
+    }
+}
+
+impl From<Move> for Action {
+    fn from(mv: Move) -> Self {
+        let action_type = match mv.promotion_piece() {
+            Some(piece_type) => ActionType::Promote(piece_type),
+            None if mv.is_castle() => ActionType::Castle,
+            None if mv.flags() == Move::EN_PASSANT => ActionType::EnPassant,
+            None => ActionType::Normal,
+        };
+
+        Action::new(mv.from(), mv.to(), Some(action_type))
+    }
+}
+
+// A fixed capacity list that lives on the stack, so generating moves doesn't
+// allocate
+#[derive(Clone)]
+pub struct MoveList {
+    moves: [Move; MAX_MOVES],
+    len: usize,
+}
+
+impl MoveList {
+    pub fn new() -> MoveList {
+        MoveList {
+            moves: [Move::default(); MAX_MOVES],
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, mv: Move) {
+        self.moves[self.len] = mv;
+        self.len += 1;
+    }
+
+    pub fn retain(&mut self, mut keep: impl FnMut(&Move) -> bool) {
+        let mut kept = 0;
+        for index in 0..self.len {
+            if keep(&self.moves[index]) {
+                self.moves[kept] = self.moves[index];
+                kept += 1;
+            }
+        }
+        self.len = kept;
+    }
+}
+
+impl Default for MoveList {
+    fn default() -> Self {
+        MoveList::new()
+    }
+}
+
+impl Deref for MoveList {
+    type Target = [Move];
+
+    fn deref(&self) -> &[Move] {
+        &self.moves[..self.len]
+    }
+}
+
+impl DerefMut for MoveList {
+    fn deref_mut(&mut self) -> &mut [Move] {
+        &mut self.moves[..self.len]
+    }
+}
+
+impl<'a> IntoIterator for &'a MoveList {
+    type Item = &'a Move;
+    type IntoIter = std::slice::Iter<'a, Move>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl std::fmt::Debug for MoveList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Move, MoveList};
+    use crate::{
+        action::{Action, ActionType},
+        board::Board,
+        piece::PieceType,
+        square::{A7, B8, D5, D6, E1, E2, E4, E5, G1, H8},
+    };
+
+    #[test]
+    fn packs_squares_and_flags() {
+        let mv = Move::new(E2, E4, Move::DOUBLE_PUSH);
+
+        assert_eq!(std::mem::size_of::<Move>(), 2);
+        assert_eq!(
+            (mv.from(), mv.to(), mv.flags()),
+            (E2, E4, Move::DOUBLE_PUSH)
+        );
+        assert!(!mv.is_capture() && !mv.is_promotion());
+
+        let mv = Move::promotion(A7, B8, PieceType::Rook, true);
+        assert!(mv.is_capture());
+        assert_eq!(mv.promotion_piece(), Some(PieceType::Rook));
+        assert_eq!(Move::new(H8, H8, 0).to(), H8);
+    }
+
+    #[test]
+    fn converts_to_and_from_actions() {
+        let board = Board::new("1r2k3/P7/8/3pP3/8/8/4P3/4K2R w K d6 0 1");
+        let actions = [
+            (Action::new(E2, E4, None), Move::DOUBLE_PUSH),
+            (
+                Action::new(E1, G1, Some(ActionType::Castle)),
+                Move::KING_CASTLE,
+            ),
+            (
+                Action::new(E5, D6, Some(ActionType::EnPassant)),
+                Move::EN_PASSANT,
+            ),
+            (
+                Action::new(A7, B8, Some(ActionType::Promote(PieceType::Queen))),
+                Move::QUEEN_PROMOTION | Move::CAPTURE,
+            ),
+            (Action::new(E5, E4, None), Move::QUIET),
+        ];
+
+        for (action, flags) in actions {
+            let mv = Move::from_action(&action, &board);
+            assert_eq!(mv.flags(), flags);
+            assert_eq!(Action::from(mv), action);
+        }
+        assert!(Move::from_action(&Action::new(E4, D5, None), &board).is_capture());
+    }
+
+    #[test]
+    fn move_list_pushes_and_retains() {
+        let mut moves = MoveList::new();
+        assert!(moves.is_empty());
+
+        moves.push(Move::new(E2, E4, Move::DOUBLE_PUSH));
+        moves.push(Move::new(E1, G1, Move::KING_CASTLE));
+        moves.push(Move::new(E4, D5, Move::CAPTURE));
+        moves.retain(|mv| !mv.is_castle());
+
+        assert_eq!(moves.len(), 2);
+        assert_eq!(moves[1].to(), D5);
+    }
+}
@@ -0,0 +1,664 @@
+use std::{
+    error, fmt,
+    io::{self, BufRead, Write},
+    vec,
+};
+
+use crate::{
+    action::MoveParseError,
+    board::{Board, DEFAULT_FEN},
+    evaluation::{MATE_BOUND, MATE_SCORE},
+    game::{Annotation, Game, GameMove, GameResult},
+    piece::Color,
+};
+
+// Export lines stay within 80 columns, newline included
+const MAX_LINE_LENGTH: usize = 79;
+
+const SEVEN_TAG_ROSTER: [(&str, &str); 7] = [
+    ("Event", "?"),
+    ("Site", "?"),
+    ("Date", "????.??.??"),
+    ("Round", "?"),
+    ("White", "?"),
+    ("Black", "?"),
+    ("Result", "*"),
+];
+
+// Written as e.g. {+0.35/12 Only move}, the form most GUIs read back
+fn annotation_comment(annotation: &Annotation) -> Option<String> {
+    let score = annotation
+        .score
+        .map(|score| match score.abs() < MATE_BOUND {
+            true => format!("{:+.2}", score as f64 / 100.0),
+            _ => {
+                let moves = (MATE_SCORE - score.abs() + 1) / 2;
+                match score > 0 {
+                    true => format!("+M{}", moves),
+                    _ => format!("-M{}", moves),
+                }
+            }
+        });
+    let analysis = match (score, annotation.depth) {
+        (Some(score), Some(depth)) => Some(format!("{}/{}", score, depth)),
+        (Some(score), None) => Some(score),
+        (None, Some(depth)) => Some(format!("/{}", depth)),
+        (None, None) => None,
+    };
+
+    match (analysis, &annotation.comment) {
+        (Some(analysis), Some(comment)) => Some(format!("{} {}", analysis, comment)),
+        (analysis, comment) => analysis.or_else(|| comment.clone()),
+    }
+}
+
+impl Game {
+    pub fn to_pgn(&self) -> String {
+        let mut output = Vec::new();
+        self.write_pgn(&mut output)
+            .expect("writing to memory cannot fail");
+        String::from_utf8(output).expect("PGN output is UTF-8")
+    }
+
+    pub fn write_pgn(&self, output: &mut impl Write) -> io::Result<()> {
+        for (name, default) in SEVEN_TAG_ROSTER {
+            let value = match name {
+                "Result" => self.result().as_pgn(),
+                _ => self.tag(name).unwrap_or(default),
+            };
+            write_tag(output, name, value)?;
+        }
+
+        let fen = self.start().to_fen();
+        if fen != DEFAULT_FEN {
+            write_tag(output, "SetUp", "1")?;
+            write_tag(output, "FEN", &fen)?;
+        }
+
+        for (name, value) in self.tags() {
+            let is_written = SEVEN_TAG_ROSTER.iter().any(|(roster, _)| roster == name)
+                || name == "SetUp"
+                || name == "FEN";
+            if !is_written {
+                write_tag(output, name, value)?;
+            }
+        }
+        if let (Some(termination), None) = (self.termination(), self.tag("Termination")) {
+            write_tag(output, "Termination", termination.as_pgn())?;
+        }
+        writeln!(output)?;
+
+        let mut tokens = Vec::new();
+        line_tokens(self.start(), self.moves(), &mut tokens);
+        tokens.push(self.result().as_pgn().to_string());
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + 1 + token.len() > MAX_LINE_LENGTH {
+                writeln!(output, "{}", line)?;
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        writeln!(output, "{}", line)?;
+        writeln!(output)
+    }
+}
+
+fn write_tag(output: &mut impl Write, name: &str, value: &str) -> io::Result<()> {
+    let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+    writeln!(output, "[{} \"{}\"]", name, value)
+}
+
+fn line_tokens(start: &Board, moves: &[GameMove], tokens: &mut Vec<String>) {
+    let mut board = start.clone();
+    // Black's moves need their number at the start of a line or after an interruption
+    let mut needs_number = true;
+
+    for game_move in moves {
+        let annotation = &game_move.annotation;
+        if let Some(comment) = &annotation.comment_before {
+            comment_tokens(comment, tokens);
+        }
+
+        match (board.side_to_move, needs_number) {
+            (Color::White, _) => tokens.push(format!("{}.", board.fullmove_number)),
+            (Color::Black, true) => tokens.push(format!("{}...", board.fullmove_number)),
+            _ => {}
+        }
+        tokens.push(board.to_san(&game_move.action));
+        tokens.extend(annotation.nags.iter().map(|nag| format!("${}", nag)));
+
+        let comment = annotation_comment(annotation);
+        if let Some(comment) = &comment {
+            comment_tokens(comment, tokens);
+        }
+        needs_number = comment.is_some() || !annotation.variations.is_empty();
+
+        for variation in &annotation.variations {
+            let first = tokens.len();
+            line_tokens(&board, variation, tokens);
+            if tokens.len() > first {
+                tokens[first].insert(0, '(');
+                if let Some(last) = tokens.last_mut() {
+                    last.push(')');
+                }
+            }
+        }
+
+        board.perform_action(game_move.action.clone());
+    }
+}
+
+// Comments are split into words so long ones can wrap
+fn comment_tokens(comment: &str, tokens: &mut Vec<String>) {
+    let comment = comment.replace('}', ")");
+    let mut words: Vec<String> = comment.split_whitespace().map(str::to_string).collect();
+    if words.is_empty() {
+        words.push(String::new());
+    }
+
+    words[0].insert(0, '{');
+    if let Some(last) = words.last_mut() {
+        last.push('}');
+    }
+    tokens.extend(words);
+}
+
+#[derive(Debug)]
+pub enum PgnError {
+    Io(io::Error),
+    // Games and plies are counted from 1
+    Syntax {
+        game: usize,
+        message: String,
+    },
+    Move {
+        game: usize,
+        ply: usize,
+        error: MoveParseError,
+    },
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PgnError::Io(error) => write!(f, "{}", error),
+            PgnError::Syntax { game, message } => write!(f, "game {}: {}", game, message),
+            PgnError::Move { game, ply, error } => {
+                write!(f, "game {}, ply {}: {}", game, ply, error)
+            }
+        }
+    }
+}
+
+impl error::Error for PgnError {}
+
+impl From<io::Error> for PgnError {
+    fn from(error: io::Error) -> Self {
+        PgnError::Io(error)
+    }
+}
+
+// Yields one game at a time, so an archive never has to fit in memory. A game
+// that fails to parse doesn't stop the games after it from being read
+pub struct PgnReader<R> {
+    lines: io::Lines<R>,
+    pending: Option<String>,
+    games_read: usize,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> PgnReader<R> {
+        PgnReader {
+            lines: reader.lines(),
+            pending: None,
+            games_read: 0,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<Game, PgnError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut tag_lines = Vec::new();
+        let mut movetext = String::new();
+        let mut in_comment = false;
+
+        loop {
+            let line = match self.pending.take() {
+                Some(line) => line,
+                None => match self.lines.next() {
+                    Some(Ok(line)) => line,
+                    Some(Err(error)) => return Some(Err(error.into())),
+                    None => break,
+                },
+            };
+            let line = line.trim();
+
+            // Escaped lines are left for other tools
+            if line.starts_with('%') {
+                continue;
+            }
+
+            if line.starts_with('[') && !in_comment {
+                // A tag after the move text starts the next game
+                if !movetext.trim().is_empty() {
+                    self.pending = Some(line.to_string());
+                    break;
+                }
+                tag_lines.push(line.to_string());
+                continue;
+            }
+
+            in_comment = ends_in_comment(line, in_comment);
+            movetext.push_str(line);
+            movetext.push('\n');
+
+            // Games without tags are separated only by their results
+            let ends_in_result = line
+                .split_whitespace()
+                .last()
+                .is_some_and(|word| GameResult::from_pgn(word).is_some());
+            if ends_in_result && !in_comment {
+                break;
+            }
+        }
+
+        if tag_lines.is_empty() && movetext.trim().is_empty() {
+            return None;
+        }
+
+        self.games_read += 1;
+        Some(parse_game(self.games_read, &tag_lines, &movetext))
+    }
+}
+
+pub fn read_games(text: &str) -> Result<Vec<Game>, PgnError> {
+    PgnReader::new(text.as_bytes()).collect()
+}
+
+fn ends_in_comment(line: &str, mut in_comment: bool) -> bool {
+    for char in line.chars() {
+        match (in_comment, char) {
+            (true, '}') => in_comment = false,
+            (false, '{') => in_comment = true,
+            (false, ';') => break,
+            _ => {}
+        }
+    }
+
+    in_comment
+}
+
+fn parse_game(game: usize, tag_lines: &[String], movetext: &str) -> Result<Game, PgnError> {
+    let tags = tag_lines
+        .iter()
+        .map(|line| {
+            parse_tag(line).ok_or_else(|| PgnError::Syntax {
+                game,
+                message: format!("invalid tag {}", line),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let fen = tags
+        .iter()
+        .find(|(name, _)| name == "FEN")
+        .map_or(DEFAULT_FEN, |(_, value)| value.as_str());
+    let start = Board::from_fen(fen).map_err(|error| PgnError::Syntax {
+        game,
+        message: format!("invalid FEN tag: {}", error),
+    })?;
+
+    let mut parser = MoveTextParser {
+        game,
+        tokens: tokenize(game, movetext)?.into_iter(),
+        result: None,
+    };
+    let moves = parser.parse_line(start.clone(), 1, false)?;
+
+    // Fall back to the tag when the move text leaves the result out
+    let result = parser.result.unwrap_or_else(|| {
+        tags.iter()
+            .find(|(name, _)| name == "Result")
+            .and_then(|(_, value)| GameResult::from_pgn(value))
+            .unwrap_or(GameResult::Unknown)
+    });
+
+    Ok(Game::from_record(start, tags, moves, result))
+}
+
+fn parse_tag(line: &str) -> Option<(String, String)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?;
+    let (name, value) = inner.split_once(char::is_whitespace)?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+
+    Some((
+        name.to_string(),
+        value.replace("\\\"", "\"").replace("\\\\", "\\"),
+    ))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Move(String),
+    Comment(String),
+    Nag(u8),
+    OpenVariation,
+    CloseVariation,
+    Result(GameResult),
+}
+
+fn syntax_error(game: usize, message: &str) -> PgnError {
+    PgnError::Syntax {
+        game,
+        message: message.to_string(),
+    }
+}
+
+fn tokenize(game: usize, movetext: &str) -> Result<Vec<Token>, PgnError> {
+    let mut tokens = Vec::new();
+    let mut chars = movetext.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '{' => {
+                let mut comment = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(char) => comment.push(char),
+                        None => return Err(syntax_error(game, "unterminated comment")),
+                    }
+                }
+                let comment = comment.split_whitespace().collect::<Vec<_>>().join(" ");
+                tokens.push(Token::Comment(comment));
+            }
+            ';' => {
+                let comment: String = chars.by_ref().take_while(|char| *char != '\n').collect();
+                tokens.push(Token::Comment(comment.trim().to_string()));
+            }
+            '(' => tokens.push(Token::OpenVariation),
+            ')' => tokens.push(Token::CloseVariation),
+            _ if char.is_whitespace() => {}
+            _ => {
+                let mut word = char.to_string();
+                while let Some(next) = chars.next_if(|next| {
+                    !next.is_whitespace() && !matches!(next, '{' | '}' | ';' | '(' | ')')
+                }) {
+                    word.push(next);
+                }
+                tokens.extend(word_token(game, &word)?);
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn word_token(game: usize, word: &str) -> Result<Option<Token>, PgnError> {
+    if let Some(nag) = word.strip_prefix('$') {
+        return nag
+            .parse()
+            .map(|nag| Some(Token::Nag(nag)))
+            .map_err(|_| syntax_error(game, &format!("invalid NAG {}", word)));
+    }
+
+    if let Some(result) = GameResult::from_pgn(word) {
+        return Ok(Some(Token::Result(result)));
+    }
+
+    // Move numbers may be glued to the move, as in 12.e4 or 12...Nf6
+    let word = match word.contains('.') {
+        true => word.trim_start_matches(|char: char| char.is_ascii_digit() || char == '.'),
+        _ => word,
+    };
+
+    match word.chars().all(|char| matches!(char, '!' | '?')) {
+        true => Ok(None),
+        _ => Ok(Some(Token::Move(word.to_string()))),
+    }
+}
+
+struct MoveTextParser {
+    game: usize,
+    tokens: vec::IntoIter<Token>,
+    result: Option<GameResult>,
+}
+
+impl MoveTextParser {
+    fn parse_line(
+        &mut self,
+        mut board: Board,
+        first_ply: usize,
+        nested: bool,
+    ) -> Result<Vec<GameMove>, PgnError> {
+        let mut moves: Vec<GameMove> = Vec::new();
+        let mut previous = board.clone();
+        let mut comment_before: Option<String> = None;
+
+        while let Some(token) = self.tokens.next() {
+            let ply = first_ply + moves.len();
+
+            match (token, moves.last_mut()) {
+                (Token::Move(san), _) => {
+                    let action = board.parse_san(&san).map_err(|error| PgnError::Move {
+                        game: self.game,
+                        ply,
+                        error,
+                    })?;
+                    previous = board.clone();
+                    board.perform_action(action.clone());
+
+                    let mut game_move = GameMove::new(action);
+                    game_move.annotation.comment_before = comment_before.take();
+                    moves.push(game_move);
+                }
+                (Token::Comment(text), None) => append_comment(&mut comment_before, text),
+                (Token::Comment(text), Some(last)) => {
+                    append_comment(&mut last.annotation.comment, text)
+                }
+                (Token::Nag(nag), Some(last)) => last.annotation.nags.push(nag),
+                (Token::OpenVariation, Some(_)) => {
+                    let variation = self.parse_line(previous.clone(), ply - 1, true)?;
+                    if let Some(last) = moves.last_mut() {
+                        last.annotation.variations.push(variation);
+                    }
+                }
+                (Token::Nag(_), None) => {
+                    return Err(syntax_error(self.game, "annotation before any move"))
+                }
+                (Token::OpenVariation, None) => {
+                    return Err(syntax_error(self.game, "variation before any move"))
+                }
+                (Token::CloseVariation, _) if nested => return Ok(moves),
+                (Token::CloseVariation, _) => {
+                    return Err(syntax_error(self.game, "unmatched closing parenthesis"))
+                }
+                (Token::Result(result), _) if !nested => self.result = Some(result),
+                (Token::Result(_), _) => {
+                    return Err(syntax_error(self.game, "result inside a variation"))
+                }
+            }
+        }
+
+        match nested {
+            true => Err(syntax_error(self.game, "unterminated variation")),
+            _ => Ok(moves),
+        }
+    }
+}
+
+fn append_comment(comment: &mut Option<String>, text: String) {
+    *comment = match comment.take() {
+        Some(existing) => Some(format!("{} {}", existing, text)),
+        None => Some(text),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_games, PgnError, PgnReader};
+    use crate::square::{D4, D7, E2, E3, E4, E5, E8};
+    use crate::{
+        action::{Action, MoveParseError},
+        board::Board,
+        evaluation::MATE_SCORE,
+        game::{Game, GameMove, GameResult, Termination},
+    };
+
+    const GAMES: &str = r#"[Event "Casual"]
+[White "Morphy, Paul"]
+[Black "Duke of Brunswick"]
+[Result "1-0"]
+
+% a line for other tools
+1. e4 e5 2. Nf3 d6 {Philidor} 3. d4 $1 Bg4 (3...exd4 4. Nxd4 (4. Qxd4 Nc6)
+4...Nf6) 4. dxe5 Bxf3 5. Qxf3 dxe5 6. Bc4 Nf6 ; threatening mate
+7. Qb3 1-0
+
+[Event "Second"]
+[Result "*"]
+
+{Opening} 1.d4 d5 2.c4 dxc4!? *
+"#;
+
+    #[test]
+    fn reads_tags_moves_comments_and_results() {
+        let games = read_games(GAMES).unwrap();
+        assert_eq!(games.len(), 2);
+
+        let game = &games[0];
+        assert_eq!(game.tag("White"), Some("Morphy, Paul"));
+        let moves = game.moves();
+        assert_eq!(moves.len(), 13);
+        assert_eq!(game.result(), GameResult::WhiteWins);
+        assert_eq!(moves[3].annotation.comment.as_deref(), Some("Philidor"));
+        assert_eq!(moves[4].annotation.nags, vec![1]);
+        assert_eq!(
+            moves[11].annotation.comment.as_deref(),
+            Some("threatening mate")
+        );
+        assert_eq!(game.positions().len(), 14);
+
+        let second = &games[1];
+        assert_eq!(second.moves().len(), 4);
+        assert_eq!(
+            second.moves()[0].annotation.comment_before.as_deref(),
+            Some("Opening")
+        );
+        assert_eq!(second.result(), GameResult::Unknown);
+    }
+
+    #[test]
+    fn reads_nested_variations() {
+        let game = &read_games(GAMES).unwrap()[0];
+
+        // 3...exd4 replaces 3...Bg4, and 4.Qxd4 replaces 4.Nxd4 inside it
+        let variation = &game.moves()[5].annotation.variations[0];
+        assert_eq!(variation[0].action, Action::new(E5, D4, None));
+        assert_eq!(variation.len(), 3);
+        assert_eq!(variation[1].annotation.variations[0].len(), 2);
+    }
+
+    #[test]
+    fn reports_the_game_and_ply_of_illegal_moves() {
+        let text = "1. e4 e5 *\n\n[Event \"Broken\"]\n\n1. e4 e5 2. Ke3 *\n\n1. d4 *\n";
+        let results: Vec<_> = PgnReader::new(text.as_bytes()).collect();
+
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        match &results[1] {
+            Err(PgnError::Move { game, ply, error }) => {
+                assert_eq!((*game, *ply), (2, 3));
+                assert_eq!(error, &MoveParseError::IllegalMove("Ke3".to_string()));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(results[2].is_ok());
+    }
+
+    #[test]
+    fn keeps_reading_after_a_malformed_fen_tag() {
+        let text = "[FEN \"kkkkkkkkk/8/8/8/8/8/8/K7 w - - 0 1\"]\n\n*\n\n1. d4 *\n";
+        let results: Vec<_> = PgnReader::new(text.as_bytes()).collect();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            results[0].as_ref().unwrap_err().to_string(),
+            "game 1: invalid FEN tag: invalid piece placement kkkkkkkkk/8/8/8/8/8/8/K7"
+        );
+        assert!(results[1].is_ok());
+    }
+
+    #[test]
+    fn reports_unbalanced_variations() {
+        let error = read_games("1. e4 (1. d4 e5").unwrap_err();
+        assert_eq!(error.to_string(), "game 1: unterminated variation");
+    }
+
+    #[test]
+    fn written_games_read_back_unchanged() {
+        for game in read_games(GAMES).unwrap() {
+            let pgn = game.to_pgn();
+            assert!(pgn.lines().all(|line| line.len() <= 79));
+
+            let reread = &read_games(&pgn).unwrap()[0];
+            assert_eq!(reread.moves(), game.moves());
+            assert_eq!(reread.result(), game.result());
+            assert_eq!(reread.tag("Event"), game.tag("Event"));
+        }
+    }
+
+    #[test]
+    fn games_read_and_write_how_they_ended() {
+        let game = &read_games("1. f3 e5 2. g4 Qh4# 0-1").unwrap()[0];
+        assert_eq!(game.result(), GameResult::BlackWins);
+        assert_eq!(game.termination(), Some(Termination::Checkmate));
+        assert!(game.to_pgn().contains("[Termination \"normal\"]"));
+
+        // A result the board can't explain has no known termination
+        let game = &read_games("1. e4 1-0").unwrap()[0];
+        assert_eq!(game.result(), GameResult::WhiteWins);
+        assert_eq!(game.termination(), None);
+    }
+
+    #[test]
+    fn writes_roster_setup_and_annotations() {
+        let start = Board::new("4k3/8/8/8/8/8/4P3/4K3 b - - 0 30");
+        let mut game = Game::new(start);
+        game.play(Action::new(E8, D7, None));
+        game.play(Action::new(E2, E4, None));
+        game.set_tag("White", "rust-krabs");
+        game.set_tag("Annotator", "rust-krabs");
+        let first = game.annotation_mut(0).unwrap();
+        first.score = Some(-35);
+        first.depth = Some(12);
+        let second = game.annotation_mut(1).unwrap();
+        second.score = Some(MATE_SCORE - 5);
+        second
+            .variations
+            .push(GameMove::line(vec![Action::new(E2, E3, None)]));
+
+        let expected = r#"[Event "?"]
+[Site "?"]
+[Date "????.??.??"]
+[Round "?"]
+[White "rust-krabs"]
+[Black "?"]
+[Result "*"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/4P3/4K3 b - - 0 30"]
+[Annotator "rust-krabs"]
+
+30... Kd7 {-0.35/12} 31. e4 {+M3} (31. e3) *
+
+"#;
+        assert_eq!(game.to_pgn(), expected);
+    }
+}
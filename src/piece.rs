@@ -1,39 +1,60 @@
-use std::str::Chars;
-
-#[derive(Debug, Clone, Copy)]
+// Squares hold an Option<Piece>, so an empty square can never pass for a black piece
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Piece {
     pub has_moved: bool,
     pub piece_type: PieceType,
-    pub is_white: bool,
+    pub color: Color,
 }
 
 impl Piece {
-    pub fn new(char: char, has_moved: bool) -> Piece {
+    pub fn new(piece_type: PieceType, color: Color) -> Piece {
         Piece {
-            has_moved,
-            piece_type: PieceType::new_from_char(&char),
-            is_white: char.is_ascii_uppercase(),
+            has_moved: false,
+            piece_type,
+            color,
         }
     }
 
-    pub fn new_empty() -> Piece {
-        Piece {
-            has_moved: false,
-            piece_type: PieceType::Empty,
-            is_white: false,
-        }
+    // FEN letters: upper case for White, lower case for Black
+    pub fn from_char(char: char) -> Option<Piece> {
+        let color = match char.is_ascii_uppercase() {
+            true => Color::White,
+            _ => Color::Black,
+        };
+        PieceType::new_from_char(&char).map(|piece_type| Piece::new(piece_type, color))
     }
 
     pub fn get_value(&self) -> i32 {
         self.piece_type.value()
     }
 
-    pub fn get_char(&self) -> char {
-        if self.is_white {
-            return self.piece_type.get_fancy_char_white();
+    // FEN letter: upper case for White, lower case for Black
+    pub fn fen_char(&self) -> char {
+        match self.color {
+            Color::White => self.piece_type.get_char().to_ascii_uppercase(),
+            Color::Black => self.piece_type.get_char(),
         }
+    }
 
-        self.piece_type.get_char()
+    pub fn unicode_char(&self) -> char {
+        match self.color {
+            Color::White => match self.piece_type {
+                PieceType::Pawn => '♙',
+                PieceType::Knight => '♘',
+                PieceType::Bishop => '♗',
+                PieceType::Rook => '♖',
+                PieceType::Queen => '♕',
+                PieceType::King => '♔',
+            },
+            Color::Black => match self.piece_type {
+                PieceType::Pawn => '♟',
+                PieceType::Knight => '♞',
+                PieceType::Bishop => '♝',
+                PieceType::Rook => '♜',
+                PieceType::Queen => '♛',
+                PieceType::King => '♚',
+            },
+        }
     }
 
     pub fn is_king(&self) -> bool {
@@ -41,7 +62,33 @@ impl Piece {
     }
 }
 
-#[derive(Default, Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Color {
+    White,
+    Black,
+}
+
+impl Color {
+    pub const BOTH: [Color; 2] = [Color::White, Color::Black];
+
+    pub const fn opposite(self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+
+    // For arrays holding one entry per colour
+    pub const fn index(self) -> usize {
+        match self {
+            Color::White => 0,
+            Color::Black => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PieceType {
     Pawn,
     Knight,
@@ -49,20 +96,18 @@ pub enum PieceType {
     Rook,
     Queen,
     King,
-    #[default]
-    Empty,
 }
 
 impl PieceType {
-    pub fn new_from_char(char: &char) -> PieceType {
+    pub fn new_from_char(char: &char) -> Option<PieceType> {
         match char.to_ascii_lowercase() {
-            'p' => PieceType::Pawn,
-            'b' => PieceType::Bishop,
-            'n' => PieceType::Knight,
-            'r' => PieceType::Rook,
-            'q' => PieceType::Queen,
-            'k' => PieceType::King,
-            _ => PieceType::Empty,
+            'p' => Some(PieceType::Pawn),
+            'b' => Some(PieceType::Bishop),
+            'n' => Some(PieceType::Knight),
+            'r' => Some(PieceType::Rook),
+            'q' => Some(PieceType::Queen),
+            'k' => Some(PieceType::King),
+            _ => None,
         }
     }
     pub fn get_char(&self) -> char {
@@ -73,19 +118,6 @@ impl PieceType {
             PieceType::Rook => 'r',
             PieceType::Queen => 'q',
             PieceType::King => 'k',
-            PieceType::Empty => ' ',
-        }
-    }
-
-    pub fn get_fancy_char_white(&self) -> char {
-        match self {
-            PieceType::Pawn => '♙',
-            PieceType::Bishop => '♗',
-            PieceType::Knight => '♘',
-            PieceType::Rook => '♖',
-            PieceType::Queen => '♕',
-            PieceType::King => '♚',
-            PieceType::Empty => ' ',
         }
     }
 
@@ -97,7 +129,45 @@ impl PieceType {
             PieceType::Queen => 9,
             PieceType::Bishop => 3,
             PieceType::Knight => 3,
-            PieceType::Empty => 0,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Color, Piece, PieceType};
+
+    #[test]
+    fn colors_have_opposites_and_indices() {
+        assert_eq!(Color::White.opposite(), Color::Black);
+        assert_eq!(Color::Black.opposite(), Color::White);
+        assert_eq!(Color::BOTH.map(Color::index), [0, 1]);
+    }
+
+    #[test]
+    fn parses_fen_letters() {
+        assert_eq!(
+            Piece::from_char('N'),
+            Some(Piece::new(PieceType::Knight, Color::White))
+        );
+        assert_eq!(
+            Piece::from_char('q'),
+            Some(Piece::new(PieceType::Queen, Color::Black))
+        );
+        assert_eq!(Piece::from_char('1'), None);
+        assert_eq!(
+            Piece::from_char('q').map(|piece| piece.fen_char()),
+            Some('q')
+        );
+    }
+
+    #[test]
+    fn unicode_glyphs_differ_by_colour() {
+        let white_king = Piece::new(PieceType::King, Color::White);
+        let black_king = Piece::new(PieceType::King, Color::Black);
+
+        assert_eq!(white_king.unicode_char(), '♔');
+        assert_eq!(black_king.unicode_char(), '♚');
+        assert_eq!(white_king.fen_char(), 'K');
+    }
+}
@@ -1,101 +1,168 @@
-use std::{cmp::min, collections::HashMap};
+use super::{
+    directions::{Direction, KNIGHT_STEPS},
+    square::Square,
+    square_set::SquareSet,
+};
 
-use super::directions::DIRECTIONS;
+// Built while compiling, so every generator and search thread shares the same
+// read-only tables for free
+pub static PRECOMPUTED: PrecomputedData = PrecomputedData::calculate();
 
 pub struct PrecomputedData {
-    pub moves_to_edge_hash: HashMap<(i32, &'static str), i32>,
-    pub moves_to_edge: [MovesToEdge; 64],
-}
-
-impl Default for PrecomputedData {
-    fn default() -> Self {
-        PrecomputedData::calculate()
-    }
+    // Squares from each square to the edge of the board, indexed by
+    // [square][direction]
+    moves_to_edge: [[u8; 8]; 64],
+    knight_targets: [SquareSet; 64],
+    king_targets: [SquareSet; 64],
+    // Indexed by [from][to]. Both are empty when the squares don't share a
+    // rank, file or diagonal.
+    between: [[SquareSet; 64]; 64],
+    line: [[SquareSet; 64]; 64],
 }
 
 impl PrecomputedData {
-    pub fn calculate() -> Self {
+    // Iterators and for loops aren't allowed in a const fn, hence the while loops
+    const fn calculate() -> Self {
         let mut data = PrecomputedData {
-            moves_to_edge_hash: HashMap::new(),
-            moves_to_edge: [MovesToEdge::default(); 64],
+            moves_to_edge: [[0; 8]; 64],
+            knight_targets: [SquareSet::EMPTY; 64],
+            king_targets: [SquareSet::EMPTY; 64],
+            between: [[SquareSet::EMPTY; 64]; 64],
+            line: [[SquareSet::EMPTY; 64]; 64],
         };
 
-        // Moves to edge
-        for file in 0..8 {
-            for rank in 0..8 {
-                let position = rank * 8 + file;
-                let moves_to_edge = MovesToEdge::new(file, rank);
-                data.moves_to_edge[position as usize] = moves_to_edge;
+        let mut index = 0;
+        while index < 64 {
+            let from = Square::from_bits(index as u16);
 
-                for dir in DIRECTIONS {
-                    data.moves_to_edge_hash
-                        .insert((position, dir), moves_to_edge.get(dir));
+            let mut jump = 0;
+            while jump < KNIGHT_STEPS.len() {
+                let (file_step, rank_step) = KNIGHT_STEPS[jump];
+                if let Some(target) = from.offset(file_step, rank_step) {
+                    data.knight_targets[index].insert(target);
                 }
+                jump += 1;
             }
+
+            let mut direction = 0;
+            while direction < Direction::ALL.len() {
+                let (file_step, rank_step) = Direction::ALL[direction].step();
+                let mut full_line = ray(from, file_step, rank_step);
+                let opposite = ray(from, -file_step, -rank_step);
+                full_line = SquareSet::from_bits(full_line.bits() | opposite.bits());
+                full_line.insert(from);
+
+                // Walk out to the edge, recording what lies between on the way
+                let mut between = SquareSet::EMPTY;
+                let mut current = from.offset(file_step, rank_step);
+                while let Some(to) = current {
+                    data.between[index][to.index()] = between;
+                    data.line[index][to.index()] = full_line;
+                    between.insert(to);
+                    current = to.offset(file_step, rank_step);
+                }
+
+                data.moves_to_edge[index][direction] = between.len() as u8;
+                if let Some(target) = from.offset(file_step, rank_step) {
+                    data.king_targets[index].insert(target);
+                }
+                direction += 1;
+            }
+
+            index += 1;
         }
 
         data
     }
 
-    pub fn get_edge_distance(&self, pos: i32, direction: &str) -> i32 {
-        self.moves_to_edge_hash
-            .get(&(pos, direction))
-            .unwrap()
-            .to_owned()
+    pub fn edge_distance(&self, pos: Square, direction: Direction) -> u8 {
+        self.moves_to_edge[pos.index()][direction.index()]
+    }
+
+    pub fn knight_targets(&self, pos: Square) -> SquareSet {
+        self.knight_targets[pos.index()]
+    }
+
+    pub fn king_targets(&self, pos: Square) -> SquareSet {
+        self.king_targets[pos.index()]
+    }
+
+    // The squares strictly between two squares on a shared rank, file or diagonal
+    pub fn between(&self, from: Square, to: Square) -> SquareSet {
+        self.between[from.index()][to.index()]
+    }
+
+    // The whole rank, file or diagonal through both squares, edge to edge
+    pub fn line(&self, from: Square, to: Square) -> SquareSet {
+        self.line[from.index()][to.index()]
     }
 }
 
-#[derive(Default, Copy, Clone, Debug)]
-pub struct MovesToEdge {
-    pub north: i32,
-    pub north_west: i32,
-    pub north_east: i32,
-    pub south: i32,
-    pub south_east: i32,
-    pub south_west: i32,
-    pub east: i32,
-    pub west: i32,
+// Every square from `from` to the edge of the board in one direction, `from`
+// itself excluded
+const fn ray(from: Square, file_step: i32, rank_step: i32) -> SquareSet {
+    let mut squares = SquareSet::EMPTY;
+    let mut current = from.offset(file_step, rank_step);
+    while let Some(square) = current {
+        squares.insert(square);
+        current = square.offset(file_step, rank_step);
+    }
+    squares
 }
 
-impl MovesToEdge {
-    fn new(file: i32, rank: i32) -> Self {
-        let i_file = file;
-        let i_rank = rank;
-
-        let south = 7 - i_rank;
-        let north = i_rank;
-        let east = 7 - i_file;
-        let west = i_file;
-
-        MovesToEdge {
-            north,
-            south,
-            east,
-            west,
-            north_east: min(north, east),
-            north_west: min(north, west),
-            south_west: min(south, west),
-            south_east: min(south, east),
-        }
+#[cfg(test)]
+mod tests {
+    use super::PRECOMPUTED;
+    use crate::{
+        directions::Direction,
+        square::{A1, B2, B3, C3, D4, E4, F6, G7, H8},
+        square_set::SquareSet,
+    };
+
+    #[test]
+    fn between_holds_the_squares_strictly_inside_a_ray() {
+        let data = &PRECOMPUTED;
+
+        assert_eq!(
+            data.between(A1, D4),
+            [B2, C3].into_iter().collect::<SquareSet>()
+        );
+        assert_eq!(data.between(D4, A1), data.between(A1, D4));
+        assert!(data.between(A1, B2).is_empty());
+        assert!(data.between(A1, B3).is_empty());
     }
 
-    pub fn get(&self, key: &str) -> i32 {
-        let val = match key.to_lowercase().as_str() {
-            "north" => Some(self.north),
-            "north_west" => Some(self.north_west),
-            "north_east" => Some(self.north_east),
-            "south" => Some(self.south),
-            "south_east" => Some(self.south_east),
-            "south_west" => Some(self.south_west),
-            "east" => Some(self.east),
-            "west" => Some(self.west),
-            _ => Some(100),
-        };
+    #[test]
+    fn line_runs_edge_to_edge_through_both_squares() {
+        let data = &PRECOMPUTED;
 
-        if let Some(value) = val {
-            value
-        } else {
-            0
-        }
+        let diagonal = data.line(C3, F6);
+        assert_eq!(diagonal.len(), 8);
+        assert!(diagonal.contains(A1) && diagonal.contains(G7) && diagonal.contains(H8));
+        assert_eq!(data.line(D4, E4).len(), 8);
+        assert!(data.line(A1, B3).is_empty());
+        assert!(data.line(A1, A1).is_empty());
+    }
+
+    #[test]
+    fn tables_cover_steps_to_the_edge() {
+        let data = &PRECOMPUTED;
+
+        assert_eq!(data.edge_distance(C3, Direction::North), 5);
+        assert_eq!(data.edge_distance(C3, Direction::SouthWest), 2);
+        assert_eq!(data.edge_distance(H8, Direction::NorthEast), 0);
+        assert_eq!(data.knight_targets(A1).len(), 2);
+        assert_eq!(data.knight_targets(D4).len(), 8);
+        assert_eq!(data.king_targets(A1).len(), 3);
+        assert!(data.king_targets(D4).contains(E4) && !data.king_targets(D4).contains(D4));
+    }
+
+    #[test]
+    fn tables_are_shared_between_threads() {
+        let from_thread = std::thread::spawn(|| PRECOMPUTED.knight_targets(A1))
+            .join()
+            .unwrap();
+
+        assert_eq!(from_thread, PRECOMPUTED.knight_targets(A1));
     }
 }
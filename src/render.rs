@@ -0,0 +1,175 @@
+use std::fmt;
+
+use crate::{board::Board, piece::Piece, square::Square};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PieceStyle {
+    // FEN letters, upper case for White
+    #[default]
+    Ascii,
+    // Chess glyphs for both colours
+    Unicode,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RenderOptions {
+    pub style: PieceStyle,
+    // Rank numbers down the left and file letters along the bottom
+    pub coordinates: bool,
+    // Draws the board from Black's side, with h1 in the top left corner
+    pub flipped: bool,
+    // Marked as (x) on the board, typically the last move's from and to squares
+    pub highlights: Vec<Square>,
+    // Marks a king in check as [k]
+    pub show_check: bool,
+}
+
+impl Board {
+    pub fn render(&self, options: &RenderOptions) -> String {
+        let checked_king = match options.show_check && self.side_to_move_in_check() {
+            true => self.king_square(self.side_to_move),
+            _ => None,
+        };
+
+        let mut ranks: Vec<u8> = (0..8).rev().collect();
+        let mut files: Vec<u8> = (0..8).collect();
+        if options.flipped {
+            ranks.reverse();
+            files.reverse();
+        }
+
+        let mut lines = Vec::new();
+        for &rank in &ranks {
+            let mut line = String::new();
+            if options.coordinates {
+                line.push_str(&format!("{} ", rank + 1));
+            }
+
+            for &file in &files {
+                let Some(square) = Square::new(file, rank) else {
+                    continue;
+                };
+                let glyph = self
+                    .piece_at(square)
+                    .map_or(empty_glyph(options.style), |piece| {
+                        piece_glyph(piece, options.style)
+                    });
+                let (open, close) = if checked_king == Some(square) {
+                    ('[', ']')
+                } else if options.highlights.contains(&square) {
+                    ('(', ')')
+                } else {
+                    (' ', ' ')
+                };
+                line.extend([open, glyph, close]);
+            }
+            lines.push(line.trim_end().to_string());
+        }
+
+        if options.coordinates {
+            let labels: String = files
+                .iter()
+                .map(|&file| format!(" {} ", (b'a' + file) as char))
+                .collect();
+            lines.push(format!("  {}", labels.trim_end()));
+        }
+
+        lines.join("\n")
+    }
+}
+
+fn piece_glyph(piece: Piece, style: PieceStyle) -> char {
+    match style {
+        PieceStyle::Ascii => piece.fen_char(),
+        PieceStyle::Unicode => piece.unicode_char(),
+    }
+}
+
+fn empty_glyph(style: PieceStyle) -> char {
+    match style {
+        PieceStyle::Ascii => '.',
+        PieceStyle::Unicode => '·',
+    }
+}
+
+// An ASCII diagram with coordinates, or Unicode glyphs with {:#}
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let style = match f.alternate() {
+            true => PieceStyle::Unicode,
+            _ => PieceStyle::Ascii,
+        };
+        let options = RenderOptions {
+            style,
+            coordinates: true,
+            ..RenderOptions::default()
+        };
+
+        write!(f, "{}", self.render(&options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PieceStyle, RenderOptions};
+    use crate::board::{Board, DEFAULT_FEN};
+    use crate::square::{E2, E4};
+
+    #[test]
+    fn displays_an_ascii_diagram_with_coordinates() {
+        let board = Board::new(DEFAULT_FEN);
+
+        let expected = [
+            "8  r  n  b  q  k  b  n  r",
+            "7  p  p  p  p  p  p  p  p",
+            "6  .  .  .  .  .  .  .  .",
+            "5  .  .  .  .  .  .  .  .",
+            "4  .  .  .  .  .  .  .  .",
+            "3  .  .  .  .  .  .  .  .",
+            "2  P  P  P  P  P  P  P  P",
+            "1  R  N  B  Q  K  B  N  R",
+            "   a  b  c  d  e  f  g  h",
+        ];
+        assert_eq!(board.to_string(), expected.join("\n"));
+    }
+
+    #[test]
+    fn flips_ranks_and_files_for_black() {
+        let board = Board::new("4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+        let options = RenderOptions {
+            flipped: true,
+            coordinates: true,
+            ..RenderOptions::default()
+        };
+
+        let rendered = board.render(&options);
+        let lines: Vec<&str> = rendered.lines().collect();
+
+        assert_eq!(lines[0], "1  .  .  .  K  .  .  .  R");
+        assert_eq!(lines[7], "8  .  .  .  k  .  .  .  .");
+        assert_eq!(lines[8], "   h  g  f  e  d  c  b  a");
+    }
+
+    #[test]
+    fn unicode_marks_highlights_and_checks() {
+        let board = Board::new("4k3/8/8/8/4P3/8/8/4R1K1 b - - 0 1");
+        let options = RenderOptions {
+            style: PieceStyle::Unicode,
+            highlights: vec![E2, E4],
+            show_check: false,
+            ..RenderOptions::default()
+        };
+        let rendered = board.render(&options);
+        assert!(rendered.contains("(♙)"));
+        assert!(rendered.contains("(·)"));
+        assert!(rendered.contains(" ♚"));
+
+        let board = Board::new("4k3/8/8/8/8/8/8/4R1K1 b - - 0 1");
+        let options = RenderOptions {
+            show_check: true,
+            ..options
+        };
+        assert!(board.render(&options).starts_with(" ·  ·  ·  · [♚]"));
+        assert!(format!("{:#}", board).contains('♖'));
+    }
+}
@@ -0,0 +1,275 @@
+use crate::{
+    action::{Action, ActionType, MoveParseError},
+    action_generator::ActionGenerator,
+    board::{Board, GameStatus},
+    piece::PieceType,
+    square::Square,
+};
+
+impl Board {
+    // Standard algebraic notation, e.g. Nbd7, exd6, e8=Q+ or O-O-O
+    pub fn to_san(&self, action: &Action) -> String {
+        let mut san = match action.action_type {
+            ActionType::Castle => match action.to > action.from {
+                true => "O-O".to_string(),
+                _ => "O-O-O".to_string(),
+            },
+            _ => self.san_body(action),
+        };
+
+        let mut after = self.clone();
+        after.perform_action(action.clone());
+        match after.status() {
+            GameStatus::Checkmate(_) => san.push('#'),
+            _ if after.side_to_move_in_check() => san.push('+'),
+            _ => {}
+        }
+
+        san
+    }
+
+    pub fn parse_san(&self, text: &str) -> Result<Action, MoveParseError> {
+        let invalid_format = || MoveParseError::InvalidFormat(text.to_string());
+        let legal_actions = ActionGenerator::default().generate_legal_actions(self);
+
+        let san = text.trim().trim_end_matches(['+', '#', '!', '?']);
+
+        let castle_king_side = match san {
+            "O-O" | "0-0" => Some(true),
+            "O-O-O" | "0-0-0" => Some(false),
+            _ => None,
+        };
+        if let Some(king_side) = castle_king_side {
+            return legal_actions
+                .into_iter()
+                .find(|action| {
+                    action.action_type.eq(&ActionType::Castle)
+                        && (action.to > action.from) == king_side
+                })
+                .ok_or_else(|| MoveParseError::IllegalMove(text.to_string()));
+        }
+
+        let mut chars: Vec<char> = san
+            .chars()
+            .filter(|char| !matches!(char, 'x' | ':' | '-' | '='))
+            .collect();
+
+        let piece_type = match chars.first() {
+            Some('K' | 'Q' | 'R' | 'B' | 'N') => {
+                PieceType::new_from_char(&chars.remove(0)).ok_or_else(invalid_format)?
+            }
+            _ => PieceType::Pawn,
+        };
+
+        // Promotions may be written e8=Q, e8Q or e8q
+        let promotion = match (piece_type, chars.as_slice()) {
+            (PieceType::Pawn, [.., rank, piece])
+                if rank.is_ascii_digit() && "QRBNqrbn".contains(*piece) =>
+            {
+                PieceType::new_from_char(&chars.pop().unwrap())
+            }
+            _ => None,
+        };
+
+        if chars.len() < 2 || chars.len() > 4 {
+            return Err(invalid_format());
+        }
+        let destination: String = chars.split_off(chars.len() - 2).into_iter().collect();
+        let to: Square = destination.parse().map_err(|_| invalid_format())?;
+
+        let mut from_file = None;
+        let mut from_rank = None;
+        for char in chars {
+            match char {
+                'a'..='h' if from_file.is_none() => from_file = Some(char as u8 - b'a'),
+                '1'..='8' if from_rank.is_none() => from_rank = Some(char as u8 - b'1'),
+                _ => return Err(invalid_format()),
+            }
+        }
+
+        let mut candidates = legal_actions.into_iter().filter(|action| {
+            let action_promotion = match action.action_type {
+                ActionType::Promote(piece_type) => Some(piece_type),
+                _ => None,
+            };
+            action.to == to
+                && !action.action_type.eq(&ActionType::Castle)
+                && self
+                    .piece_at(action.from)
+                    .is_some_and(|piece| piece.piece_type.eq(&piece_type))
+                && action_promotion == promotion
+                && from_file.is_none_or(|file| action.from.file() == file)
+                && from_rank.is_none_or(|rank| action.from.rank() == rank)
+        });
+
+        match (candidates.next(), candidates.next()) {
+            (Some(action), None) => Ok(action),
+            (Some(_), Some(_)) => Err(MoveParseError::AmbiguousMove(text.to_string())),
+            _ => Err(MoveParseError::IllegalMove(text.to_string())),
+        }
+    }
+
+    fn san_body(&self, action: &Action) -> String {
+        let Some(piece) = self.piece_at(action.from) else {
+            return action.to_uci();
+        };
+        let piece_type = piece.piece_type;
+        let is_capture =
+            action.action_type.eq(&ActionType::EnPassant) || self.piece_at(action.to).is_some();
+        let capture = match is_capture {
+            true => "x",
+            _ => "",
+        };
+        let destination = action.to.to_string();
+        let from = action.from.to_string();
+
+        if piece_type.eq(&PieceType::Pawn) {
+            let file = match is_capture {
+                true => &from[0..1],
+                _ => "",
+            };
+            let promotion = match action.action_type {
+                ActionType::Promote(piece_type) => {
+                    format!("={}", piece_type.get_char().to_ascii_uppercase())
+                }
+                _ => String::new(),
+            };
+            return format!("{}{}{}{}", file, capture, destination, promotion);
+        }
+
+        // Other pieces of the same type that could also reach the destination
+        let rivals: Vec<Square> = ActionGenerator::default()
+            .generate_legal_actions(self)
+            .into_iter()
+            .filter(|other| {
+                other.to == action.to
+                    && other.from != action.from
+                    && self
+                        .piece_at(other.from)
+                        .is_some_and(|piece| piece.piece_type.eq(&piece_type))
+            })
+            .map(|other| other.from)
+            .collect();
+
+        let disambiguation = match (
+            rivals.is_empty(),
+            rivals.iter().any(|from| from.file() == action.from.file()),
+            rivals.iter().any(|from| from.rank() == action.from.rank()),
+        ) {
+            (true, _, _) => "",
+            (_, false, _) => &from[0..1],
+            (_, _, false) => &from[1..2],
+            _ => &from[..],
+        };
+
+        format!(
+            "{}{}{}{}",
+            piece_type.get_char().to_ascii_uppercase(),
+            disambiguation,
+            capture,
+            destination
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::square::{A1, A2, A4, A8, B2, B7, C1, D1, D4, D6, E1, E5, F1, F3, G1, G5, H1};
+    use crate::{
+        action::{Action, ActionType, MoveParseError},
+        action_generator::ActionGenerator,
+        board::Board,
+        piece::PieceType,
+    };
+
+    #[test]
+    fn to_san_formats_pieces_pawns_and_castling() {
+        let board = Board::new("r3k2r/1P6/8/3pP3/8/5N2/8/R3K2R w KQkq d6 0 1");
+
+        assert_eq!(board.to_san(&Action::new(F3, G5, None)), "Ng5");
+        assert_eq!(
+            board.to_san(&Action::new(E5, D6, Some(ActionType::EnPassant))),
+            "exd6"
+        );
+        assert_eq!(
+            board.to_san(&Action::new(
+                B7,
+                A8,
+                Some(ActionType::Promote(PieceType::Queen))
+            )),
+            "bxa8=Q+"
+        );
+        assert_eq!(
+            board.to_san(&Action::new(E1, G1, Some(ActionType::Castle))),
+            "O-O"
+        );
+        assert_eq!(
+            board.to_san(&Action::new(E1, C1, Some(ActionType::Castle))),
+            "O-O-O"
+        );
+    }
+
+    #[test]
+    fn to_san_disambiguates_by_file_rank_or_both() {
+        let board = Board::new("6k1/8/8/8/Q2Q4/8/8/Q3K2R w - - 0 1");
+
+        assert_eq!(board.to_san(&Action::new(A1, C1, None)), "Qc1");
+        assert_eq!(board.to_san(&Action::new(H1, F1, None)), "Rf1");
+        assert_eq!(board.to_san(&Action::new(A4, A2, None)), "Q4a2+");
+        assert_eq!(board.to_san(&Action::new(D4, B2, None)), "Qdb2");
+        assert_eq!(board.to_san(&Action::new(A4, D1, None)), "Qa4d1");
+    }
+
+    #[test]
+    fn to_san_marks_checkmate() {
+        let board = Board::new("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        assert_eq!(board.to_san(&Action::new(A1, A8, None)), "Ra8#");
+    }
+
+    #[test]
+    fn parse_san_tolerates_common_variants() {
+        let board = Board::new("r3k2r/1P6/8/3pP3/8/5N2/8/R3K2R w KQkq d6 0 1");
+
+        assert_eq!(board.parse_san("Ng5!?").unwrap(), Action::new(F3, G5, None));
+        assert_eq!(
+            board.parse_san("ed6").unwrap(),
+            Action::new(E5, D6, Some(ActionType::EnPassant))
+        );
+        assert_eq!(
+            board.parse_san("bxa8Q+").unwrap(),
+            Action::new(B7, A8, Some(ActionType::Promote(PieceType::Queen)))
+        );
+        assert_eq!(
+            board.parse_san("0-0-0").unwrap(),
+            Action::new(E1, C1, Some(ActionType::Castle))
+        );
+    }
+
+    #[test]
+    fn parse_san_round_trips_every_legal_action() {
+        let board = Board::new("r3k2r/1P6/8/3pP3/8/Q4N1Q/8/R3K2R w KQkq d6 0 1");
+        let actions = ActionGenerator::default().generate_legal_actions(&board);
+
+        for action in actions {
+            assert_eq!(board.parse_san(&board.to_san(&action)).unwrap(), action);
+        }
+    }
+
+    #[test]
+    fn parse_san_rejects_bad_input() {
+        let board = Board::new("6k1/8/8/8/Q2Q4/8/8/Q3K2R w - - 0 1");
+
+        assert_eq!(
+            board.parse_san("Qd1"),
+            Err(MoveParseError::AmbiguousMove("Qd1".to_string()))
+        );
+        assert_eq!(
+            board.parse_san("Nf3"),
+            Err(MoveParseError::IllegalMove("Nf3".to_string()))
+        );
+        assert_eq!(
+            board.parse_san("hello"),
+            Err(MoveParseError::InvalidFormat("hello".to_string()))
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{
+    action::{Action, ActionType},
+    board::Board,
+    piece::PieceType,
+    square::Square,
+};
+
+// Boards travel as FEN
+impl Serialize for Board {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_fen())
+    }
+}
+
+impl<'de> Deserialize<'de> for Board {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let fen = String::deserialize(deserializer)?;
+        Board::from_fen(&fen).map_err(de::Error::custom)
+    }
+}
+
+// Actions travel as UCI text, e.g. e2e4 or e7e8q
+impl Serialize for Action {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_uci())
+    }
+}
+
+// Without a board castling and en passant can't be told apart from ordinary
+// moves, so those come back as Normal. Board::parse_uci_move on the action's
+// to_uci() recovers the full action when the position is known.
+impl<'de> Deserialize<'de> for Action {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        let invalid = || de::Error::invalid_value(de::Unexpected::Str(&text), &"a UCI move");
+
+        let (from, to, promotion) = match (text.get(0..2), text.get(2..4), text.get(4..)) {
+            (Some(from), Some(to), Some(promotion)) => (from, to, promotion),
+            _ => return Err(invalid()),
+        };
+        let from: Square = from.parse().map_err(|_| invalid())?;
+        let to: Square = to.parse().map_err(|_| invalid())?;
+
+        let action_type = match promotion.chars().collect::<Vec<_>>().as_slice() {
+            [] => None,
+            [char] => match PieceType::new_from_char(char) {
+                Some(
+                    piece_type @ (PieceType::Knight
+                    | PieceType::Bishop
+                    | PieceType::Rook
+                    | PieceType::Queen),
+                ) => Some(ActionType::Promote(piece_type)),
+                _ => return Err(invalid()),
+            },
+            _ => return Err(invalid()),
+        };
+
+        Ok(Action::new(from, to, action_type))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::square::{E2, E4, E7, E8};
+    use crate::{
+        action::{Action, ActionType},
+        board::{Board, DEFAULT_FEN},
+        engine::SearchResult,
+        piece::PieceType,
+    };
+
+    #[test]
+    fn boards_round_trip_as_fen() {
+        let fen = "r3k2r/1P6/8/3pP3/8/5N2/8/R3K2R w Kq d6 0 17";
+        let json = serde_json::to_string(&Board::new(fen)).unwrap();
+        assert_eq!(json, format!("\"{}\"", fen));
+
+        let board: Board = serde_json::from_str(&json).unwrap();
+        assert_eq!(board.to_fen(), fen);
+
+        assert!(serde_json::from_str::<Board>("\"\"").is_err());
+        assert!(serde_json::from_str::<Board>("\"rnbqkbnr/pppppppp/9/8 w - - 0 1\"").is_err());
+        assert!(serde_json::from_str::<Board>("\"4k3/8/8/8/8/8/8/4K3 white - - 0 1\"").is_err());
+        assert!(serde_json::from_str::<Board>("\"4k3/8/8/8/8/8/8/4K3 w KX - 0 1\"").is_err());
+        assert!(serde_json::from_str::<Board>(&format!("\"{}\"", DEFAULT_FEN)).is_ok());
+    }
+
+    #[test]
+    fn actions_round_trip_as_uci() {
+        let promotion = Action::new(E7, E8, Some(ActionType::Promote(PieceType::Knight)));
+        let json = serde_json::to_string(&vec![Action::new(E2, E4, None), promotion.clone()]);
+        assert_eq!(json.unwrap(), r#"["e2e4","e7e8n"]"#);
+
+        let action: Action = serde_json::from_str("\"e7e8n\"").unwrap();
+        assert_eq!(action, promotion);
+        for text in ["\"e2\"", "\"e2e9\"", "\"e7e8k\"", "\"e7e8qq\""] {
+            assert!(serde_json::from_str::<Action>(text).is_err());
+        }
+    }
+
+    #[test]
+    fn search_results_serialize_with_the_best_action_as_uci() {
+        let result = SearchResult {
+            best_action: Some(Action::new(E2, E4, None)),
+            score: 35,
+            depth: 6,
+            nodes: 12_345,
+            time: Duration::from_millis(250),
+        };
+        let json = serde_json::to_value(&result).unwrap();
+
+        assert_eq!(json["best_action"], "e2e4");
+        assert_eq!(json["score"], 35);
+
+        let back: SearchResult = serde_json::from_value(json).unwrap();
+        assert_eq!(back.best_action, result.best_action);
+        assert_eq!(back.time, result.time);
+    }
+}
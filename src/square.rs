@@ -0,0 +1,195 @@
+use std::{error, fmt, str::FromStr};
+
+// A square on the board, indexed rank by rank from a1 = 0 to h8 = 63
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Square(u8);
+
+impl Square {
+    pub const fn new(file: u8, rank: u8) -> Option<Square> {
+        match file < 8 && rank < 8 {
+            true => Some(Square(rank * 8 + file)),
+            _ => None,
+        }
+    }
+
+    pub const fn from_index(index: usize) -> Option<Square> {
+        match index < 64 {
+            true => Some(Square(index as u8)),
+            _ => None,
+        }
+    }
+
+    // Only the low six bits are read, so any value names a square
+    pub(crate) const fn from_bits(bits: u16) -> Square {
+        Square((bits & 63) as u8)
+    }
+
+    pub const fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    // 0 for the a-file through 7 for the h-file
+    pub const fn file(self) -> u8 {
+        self.0 % 8
+    }
+
+    // 0 for the first rank through 7 for the eighth
+    pub const fn rank(self) -> u8 {
+        self.0 / 8
+    }
+
+    // Steps towards the h-file and the eighth rank, or None past the edge
+    pub const fn offset(self, files: i32, ranks: i32) -> Option<Square> {
+        let file = self.file() as i32 + files;
+        let rank = self.rank() as i32 + ranks;
+
+        match file >= 0 && file < 8 && rank >= 0 && rank < 8 {
+            true => Square::new(file as u8, rank as u8),
+            _ => None,
+        }
+    }
+
+    // The same square seen from the other side of the board, e.g. e2 and e7
+    pub const fn mirror(self) -> Square {
+        Square(self.0 ^ 56)
+    }
+
+    pub fn all() -> impl Iterator<Item = Square> {
+        (0..64).map(Square)
+    }
+}
+
+impl fmt::Display for Square {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", (b'a' + self.file()) as char, self.rank() + 1)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseSquareError(String);
+
+impl fmt::Display for ParseSquareError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid square {}", self.0)
+    }
+}
+
+impl error::Error for ParseSquareError {}
+
+impl FromStr for Square {
+    type Err = ParseSquareError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let error = || ParseSquareError(text.to_string());
+
+        let [file @ b'a'..=b'h', rank @ b'1'..=b'8'] = text.as_bytes() else {
+            return Err(error());
+        };
+        Square::new(file - b'a', rank - b'1').ok_or_else(error)
+    }
+}
+
+pub const A1: Square = Square(0);
+pub const B1: Square = Square(1);
+pub const C1: Square = Square(2);
+pub const D1: Square = Square(3);
+pub const E1: Square = Square(4);
+pub const F1: Square = Square(5);
+pub const G1: Square = Square(6);
+pub const H1: Square = Square(7);
+pub const A2: Square = Square(8);
+pub const B2: Square = Square(9);
+pub const C2: Square = Square(10);
+pub const D2: Square = Square(11);
+pub const E2: Square = Square(12);
+pub const F2: Square = Square(13);
+pub const G2: Square = Square(14);
+pub const H2: Square = Square(15);
+pub const A3: Square = Square(16);
+pub const B3: Square = Square(17);
+pub const C3: Square = Square(18);
+pub const D3: Square = Square(19);
+pub const E3: Square = Square(20);
+pub const F3: Square = Square(21);
+pub const G3: Square = Square(22);
+pub const H3: Square = Square(23);
+pub const A4: Square = Square(24);
+pub const B4: Square = Square(25);
+pub const C4: Square = Square(26);
+pub const D4: Square = Square(27);
+pub const E4: Square = Square(28);
+pub const F4: Square = Square(29);
+pub const G4: Square = Square(30);
+pub const H4: Square = Square(31);
+pub const A5: Square = Square(32);
+pub const B5: Square = Square(33);
+pub const C5: Square = Square(34);
+pub const D5: Square = Square(35);
+pub const E5: Square = Square(36);
+pub const F5: Square = Square(37);
+pub const G5: Square = Square(38);
+pub const H5: Square = Square(39);
+pub const A6: Square = Square(40);
+pub const B6: Square = Square(41);
+pub const C6: Square = Square(42);
+pub const D6: Square = Square(43);
+pub const E6: Square = Square(44);
+pub const F6: Square = Square(45);
+pub const G6: Square = Square(46);
+pub const H6: Square = Square(47);
+pub const A7: Square = Square(48);
+pub const B7: Square = Square(49);
+pub const C7: Square = Square(50);
+pub const D7: Square = Square(51);
+pub const E7: Square = Square(52);
+pub const F7: Square = Square(53);
+pub const G7: Square = Square(54);
+pub const H7: Square = Square(55);
+pub const A8: Square = Square(56);
+pub const B8: Square = Square(57);
+pub const C8: Square = Square(58);
+pub const D8: Square = Square(59);
+pub const E8: Square = Square(60);
+pub const F8: Square = Square(61);
+pub const G8: Square = Square(62);
+pub const H8: Square = Square(63);
+
+#[cfg(test)]
+mod tests {
+    use super::{Square, A1, A8, E2, E4, E7, H1, H8};
+
+    #[test]
+    fn exposes_file_and_rank() {
+        assert_eq!((E4.file(), E4.rank()), (4, 3));
+        assert_eq!(Square::new(4, 3), Some(E4));
+        assert_eq!(Square::new(8, 0), None);
+        assert_eq!(Square::from_index(63), Some(H8));
+        assert_eq!(Square::from_index(64), None);
+    }
+
+    #[test]
+    fn parses_and_formats_algebraic() {
+        assert_eq!("e4".parse::<Square>(), Ok(E4));
+        assert_eq!(H8.to_string(), "h8");
+        for text in ["", "e", "i4", "e9", "e44", "E4"] {
+            assert!(text.parse::<Square>().is_err());
+        }
+        assert!(Square::all().all(|square| square.to_string().parse() == Ok(square)));
+    }
+
+    #[test]
+    fn offsets_stop_at_the_edges() {
+        assert_eq!(E2.offset(0, 2), Some(E4));
+        assert_eq!(H1.offset(1, 0), None);
+        assert_eq!(A1.offset(-1, 1), None);
+        assert_eq!(A8.offset(0, 1), None);
+        assert_eq!(A1.offset(7, 7), Some(H8));
+    }
+
+    #[test]
+    fn mirrors_across_the_middle() {
+        assert_eq!(E2.mirror(), E7);
+        assert_eq!(A1.mirror(), A8);
+        assert_eq!(Square::all().count(), 64);
+    }
+}
@@ -0,0 +1,146 @@
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not};
+
+use crate::square::Square;
+
+// A set of squares packed into one bit per square, a1 in the lowest bit
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SquareSet(u64);
+
+impl SquareSet {
+    pub const EMPTY: SquareSet = SquareSet(0);
+    pub const ALL: SquareSet = SquareSet(u64::MAX);
+
+    pub const fn from_bits(bits: u64) -> SquareSet {
+        SquareSet(bits)
+    }
+
+    pub const fn bits(self) -> u64 {
+        self.0
+    }
+
+    pub const fn contains(self, square: Square) -> bool {
+        self.0 & 1 << square.index() != 0
+    }
+
+    pub const fn insert(&mut self, square: Square) {
+        self.0 |= 1 << square.index();
+    }
+
+    pub const fn remove(&mut self, square: Square) {
+        self.0 &= !(1 << square.index());
+    }
+
+    pub const fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    // The lowest square in the set, counting from a1
+    pub const fn first(self) -> Option<Square> {
+        match self.0 {
+            0 => None,
+            bits => Some(Square::from_bits(bits.trailing_zeros() as u16)),
+        }
+    }
+}
+
+impl From<Square> for SquareSet {
+    fn from(square: Square) -> Self {
+        SquareSet(1 << square.index())
+    }
+}
+
+impl FromIterator<Square> for SquareSet {
+    fn from_iter<I: IntoIterator<Item = Square>>(squares: I) -> Self {
+        let mut set = SquareSet::EMPTY;
+        for square in squares {
+            set.insert(square);
+        }
+        set
+    }
+}
+
+impl BitOr for SquareSet {
+    type Output = SquareSet;
+
+    fn bitor(self, other: SquareSet) -> SquareSet {
+        SquareSet(self.0 | other.0)
+    }
+}
+
+impl BitOrAssign for SquareSet {
+    fn bitor_assign(&mut self, other: SquareSet) {
+        self.0 |= other.0;
+    }
+}
+
+impl BitAnd for SquareSet {
+    type Output = SquareSet;
+
+    fn bitand(self, other: SquareSet) -> SquareSet {
+        SquareSet(self.0 & other.0)
+    }
+}
+
+impl BitAndAssign for SquareSet {
+    fn bitand_assign(&mut self, other: SquareSet) {
+        self.0 &= other.0;
+    }
+}
+
+impl Not for SquareSet {
+    type Output = SquareSet;
+
+    fn not(self) -> SquareSet {
+        SquareSet(!self.0)
+    }
+}
+
+impl Iterator for SquareSet {
+    type Item = Square;
+
+    fn next(&mut self) -> Option<Square> {
+        let square = self.first()?;
+        // Clears the lowest set bit
+        self.0 &= self.0 - 1;
+        Some(square)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len(), Some(self.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SquareSet;
+    use crate::square::{Square, A1, D4, E4, H8};
+
+    #[test]
+    fn inserts_and_removes_squares() {
+        let mut set = SquareSet::EMPTY;
+        set.insert(E4);
+        set.insert(A1);
+        set.insert(E4);
+
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(E4) && !set.contains(D4));
+        assert_eq!(set.first(), Some(A1));
+
+        set.remove(A1);
+        assert_eq!(set, SquareSet::from(E4));
+    }
+
+    #[test]
+    fn iterates_from_a1_to_h8() {
+        let set: SquareSet = [H8, D4, A1].into_iter().collect();
+
+        assert_eq!(set.collect::<Vec<_>>(), vec![A1, D4, H8]);
+        assert_eq!(SquareSet::ALL.count(), 64);
+        assert!(SquareSet::ALL.eq(Square::all()));
+        assert_eq!(!SquareSet::ALL & set, SquareSet::EMPTY);
+    }
+}
@@ -0,0 +1,245 @@
+use std::fmt::Write;
+
+use crate::{
+    board::Board,
+    piece::{Color, PieceType},
+    square::Square,
+};
+
+// Each square is 45 units across in the viewBox, and the piece shapes below
+// are drawn to fit one square
+const SQUARE_SIZE: u32 = 45;
+const MARGIN: u32 = 20;
+
+const LIGHT_SQUARE: &str = "#f0d9b5";
+const DARK_SQUARE: &str = "#b58863";
+const HIGHLIGHT: &str = "#9bc700";
+const ARROW: &str = "#15781b";
+
+// Outlines only, so the fill and stroke come from the <use> for each colour
+const PIECE_SHAPES: [(PieceType, &str); 6] = [
+    (
+        PieceType::Pawn,
+        r#"<circle cx="22.5" cy="15" r="6"/><path d="M19 21h7l4 13h-15z"/><rect x="12" y="34" width="21" height="5"/>"#,
+    ),
+    (
+        PieceType::Knight,
+        r#"<path d="M14 34l3-9 6-4-7 1-3-3 2-6 8-6 3 3 5 5 2 9-1 10z"/><rect x="11" y="34" width="23" height="5"/>"#,
+    ),
+    (
+        PieceType::Bishop,
+        r#"<circle cx="22.5" cy="9" r="3"/><ellipse cx="22.5" cy="23" rx="7" ry="11"/><rect x="11" y="34" width="23" height="5"/>"#,
+    ),
+    (
+        PieceType::Rook,
+        r#"<path d="M12 9h4v3h4v-3h5v3h4v-3h4v8h-21z"/><rect x="15" y="17" width="15" height="17"/><rect x="11" y="34" width="23" height="5"/>"#,
+    ),
+    (
+        PieceType::Queen,
+        r#"<path d="M11 32l-3-19 8 11 6.5-14 6.5 14 8-11-3 19z"/><circle cx="8" cy="12" r="2"/><circle cx="22.5" cy="9" r="2"/><circle cx="37" cy="12" r="2"/><rect x="11" y="32" width="23" height="6"/>"#,
+    ),
+    (
+        PieceType::King,
+        r#"<path d="M21 5h3v4h4v3h-4v6h-3v-6h-4v-3h4z"/><path d="M13 33l-3-13 12.5 4 12.5-4-3 13z"/><rect x="11" y="33" width="23" height="6"/>"#,
+    ),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SvgOptions {
+    // Width and height of the image in pixels
+    pub size: u32,
+    pub coordinates: bool,
+    // Draws the board from Black's side
+    pub flipped: bool,
+    // Tinted squares, e.g. the last move or the squares a piece attacks
+    pub highlights: Vec<Square>,
+    // (from, to) pairs, e.g. the best move
+    pub arrows: Vec<(Square, Square)>,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions {
+            size: 400,
+            coordinates: true,
+            flipped: false,
+            highlights: Vec::new(),
+            arrows: Vec::new(),
+        }
+    }
+}
+
+impl SvgOptions {
+    // Top left corner of a square in viewBox units
+    fn origin(&self, square: Square) -> (u32, u32) {
+        let (column, row) = match self.flipped {
+            true => (7 - square.file(), square.rank()),
+            _ => (square.file(), 7 - square.rank()),
+        };
+        (
+            self.margin() + column as u32 * SQUARE_SIZE,
+            row as u32 * SQUARE_SIZE,
+        )
+    }
+
+    fn centre(&self, square: Square) -> (f32, f32) {
+        let (x, y) = self.origin(square);
+        let half = SQUARE_SIZE as f32 / 2.0;
+        (x as f32 + half, y as f32 + half)
+    }
+
+    fn margin(&self) -> u32 {
+        match self.coordinates {
+            true => MARGIN,
+            _ => 0,
+        }
+    }
+}
+
+impl Board {
+    pub fn to_svg(&self, options: &SvgOptions) -> String {
+        let extent = 8 * SQUARE_SIZE + options.margin();
+        let mut svg = String::new();
+
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" width="{size}" height="{size}" viewBox="0 0 {extent} {extent}">"#,
+            size = options.size,
+        );
+        write_defs(&mut svg);
+
+        for square in Square::all() {
+            let (x, y) = options.origin(square);
+            let fill = match (square.file() + square.rank()) % 2 {
+                0 => DARK_SQUARE,
+                _ => LIGHT_SQUARE,
+            };
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{x}" y="{y}" width="{SQUARE_SIZE}" height="{SQUARE_SIZE}" fill="{fill}"/>"#,
+            );
+            if options.highlights.contains(&square) {
+                let _ = writeln!(
+                    svg,
+                    r#"<rect class="highlight" x="{x}" y="{y}" width="{SQUARE_SIZE}" height="{SQUARE_SIZE}" fill="{HIGHLIGHT}" fill-opacity="0.5"/>"#,
+                );
+            }
+        }
+
+        if options.coordinates {
+            write_coordinates(&mut svg, options);
+        }
+
+        for square in Square::all() {
+            let Some(piece) = self.piece_at(square) else {
+                continue;
+            };
+            let (x, y) = options.origin(square);
+            let (fill, stroke) = match piece.color {
+                Color::White => ("#fff", "#000"),
+                Color::Black => ("#000", "#fff"),
+            };
+            let _ = writeln!(
+                svg,
+                r##"<use xlink:href="#{id}" href="#{id}" x="{x}" y="{y}" fill="{fill}" stroke="{stroke}" stroke-width="1.5"/>"##,
+                id = piece.piece_type.get_char(),
+            );
+        }
+
+        for &(from, to) in &options.arrows {
+            let (x1, y1) = options.centre(from);
+            let (x2, y2) = options.centre(to);
+            let _ = writeln!(
+                svg,
+                r#"<line class="arrow {from}{to}" x1="{x1}" y1="{y1}" x2="{x2}" y2="{y2}" stroke="{ARROW}" stroke-width="7" stroke-opacity="0.8" stroke-linecap="round" marker-end="url(#arrowhead)"/>"#,
+            );
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+}
+
+fn write_defs(svg: &mut String) {
+    svg.push_str("<defs>\n");
+    for (piece_type, shape) in PIECE_SHAPES {
+        let _ = writeln!(svg, r#"<g id="{}">{}</g>"#, piece_type.get_char(), shape);
+    }
+    let _ = writeln!(
+        svg,
+        r#"<marker id="arrowhead" markerWidth="4" markerHeight="4" refX="2" refY="2" orient="auto"><path d="M0 0L4 2L0 4z" fill="{ARROW}"/></marker>"#,
+    );
+    svg.push_str("</defs>\n");
+}
+
+// Rank numbers in the left margin and file letters in the bottom one
+fn write_coordinates(svg: &mut String, options: &SvgOptions) {
+    let half = MARGIN / 2;
+    for index in 0..8u8 {
+        let Some(square) = Square::new(index, index) else {
+            continue;
+        };
+        let (x, y) = options.origin(square);
+        let _ = writeln!(
+            svg,
+            r#"<text x="{half}" y="{}" font-family="sans-serif" font-size="14" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+            y + SQUARE_SIZE / 2,
+            index + 1,
+        );
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}" font-family="sans-serif" font-size="14" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+            x + SQUARE_SIZE / 2,
+            8 * SQUARE_SIZE + half,
+            (b'a' + index) as char,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SvgOptions;
+    use crate::board::{Board, DEFAULT_FEN};
+    use crate::square::{A1, E2, E4, H8};
+
+    #[test]
+    fn draws_every_piece_and_square() {
+        let board = Board::new(DEFAULT_FEN);
+        let svg = board.to_svg(&SvgOptions::default());
+
+        assert!(svg.starts_with("<svg") && svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<use ").count(), 32);
+        assert_eq!(svg.matches(r#"width="45""#).count(), 64);
+        assert!(svg.contains(">a</text>") && svg.contains(">8</text>"));
+        assert!(!svg.contains("class=\"highlight\""));
+    }
+
+    #[test]
+    fn flipping_puts_h8_in_the_bottom_left() {
+        let board = Board::new("7k/8/8/8/8/8/8/K7 w - - 0 1");
+        let options = SvgOptions {
+            coordinates: false,
+            flipped: true,
+            ..SvgOptions::default()
+        };
+
+        assert_eq!(options.origin(H8), (0, 315));
+        assert_eq!(options.origin(A1), (315, 0));
+        let svg = board.to_svg(&options);
+        assert!(svg.contains(r##"href="#k" x="0" y="315""##));
+    }
+
+    #[test]
+    fn draws_highlights_and_arrows() {
+        let board = Board::new(DEFAULT_FEN);
+        let options = SvgOptions {
+            highlights: vec![E2, E4],
+            arrows: vec![(E2, E4)],
+            ..SvgOptions::default()
+        };
+        let svg = board.to_svg(&options);
+
+        assert_eq!(svg.matches("class=\"highlight\"").count(), 2);
+        assert!(svg.contains(r#"class="arrow e2e4" x1="222.5" y1="292.5" x2="222.5" y2="202.5""#));
+    }
+}
@@ -0,0 +1,574 @@
+use std::{
+    io::{BufRead, Write},
+    sync::{atomic::Ordering, Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::{
+    action::Action,
+    board::{Board, DEFAULT_FEN},
+    engine::{
+        allocate_time as allocate_clock_time, Engine, SearchInfo, SearchResult, SearchSignals,
+        MAX_PLY, MOVE_OVERHEAD,
+    },
+    evaluation::{MATE_BOUND, MATE_SCORE},
+    game::Game,
+    piece::Color,
+};
+
+const GO_KEYWORDS: [&str; 12] = [
+    "searchmoves",
+    "ponder",
+    "wtime",
+    "btime",
+    "winc",
+    "binc",
+    "movestogo",
+    "depth",
+    "nodes",
+    "mate",
+    "movetime",
+    "infinite",
+];
+
+pub type SharedOutput = Arc<Mutex<dyn Write + Send>>;
+
+pub fn run(input: impl BufRead, output: SharedOutput) {
+    let mut uci = Uci::new(output);
+
+    for line in input.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+
+        if !uci.handle_command(&line) {
+            break;
+        }
+    }
+
+    uci.stop();
+}
+
+pub struct Uci {
+    // Lent to the worker thread while a search is running
+    engine: Option<Engine>,
+    worker: Option<JoinHandle<Engine>>,
+    signals: Arc<SearchSignals>,
+    output: SharedOutput,
+}
+
+impl Uci {
+    pub fn new(output: SharedOutput) -> Self {
+        let engine = Engine::new(DEFAULT_FEN);
+        let signals = engine.signals();
+
+        Uci {
+            engine: Some(engine),
+            worker: None,
+            signals,
+            output,
+        }
+    }
+
+    // Returns false once the GUI has asked us to quit
+    pub fn handle_command(&mut self, line: &str) -> bool {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+
+        match tokens.first() {
+            Some(&"uci") => self.identify(),
+            Some(&"isready") => self.send("readyok"),
+            Some(&"ucinewgame") => self.engine().set_board(Board::new(DEFAULT_FEN)),
+            Some(&"position") => self.set_position(&tokens[1..]),
+            Some(&"go") => self.go(&tokens[1..]),
+            Some(&"stop") => self.stop(),
+            Some(&"ponderhit") => self.signals.ponder.store(false, Ordering::Relaxed),
+            Some(&"setoption") => self.set_option(&tokens[1..]),
+            Some(&"quit") => return false,
+            Some(&"debug") | Some(&"register") | None => {}
+            Some(command) => self.send(&format!("info string unknown command {}", command)),
+        }
+
+        true
+    }
+
+    pub fn stop(&mut self) {
+        self.signals.stop.store(true, Ordering::Relaxed);
+        self.wait_for_search();
+    }
+
+    // Lets a finite search run to completion, for callers driving the engine
+    // without a GUI
+    pub fn wait_for_search(&mut self) {
+        if let Some(worker) = self.worker.take() {
+            self.engine = Some(worker.join().expect("search thread panicked"));
+        }
+    }
+
+    fn identify(&self) {
+        self.send(&format!(
+            "id name {} {}",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        ));
+        self.send("id author the RustyKrabs developers");
+        self.send("option name Ponder type check default false");
+        self.send("option name NullMoveVerification type check default true");
+        self.send("uciok");
+    }
+
+    // Any running search is stopped before the engine is handed back
+    fn engine(&mut self) -> &mut Engine {
+        self.stop();
+        self.engine
+            .as_mut()
+            .expect("engine is owned by the front end")
+    }
+
+    fn set_position(&mut self, tokens: &[&str]) {
+        let moves_at = tokens
+            .iter()
+            .position(|&token| token == "moves")
+            .unwrap_or(tokens.len());
+
+        let fen = match tokens.first() {
+            Some(&"startpos") => DEFAULT_FEN.to_string(),
+            Some(&"fen") => tokens[1..moves_at].join(" "),
+            _ => {
+                self.send("info string expected startpos or fen");
+                return;
+            }
+        };
+
+        let board = match Board::from_fen(&fen) {
+            Ok(board) => board,
+            Err(error) => {
+                self.send(&format!("info string invalid fen {}", error));
+                return;
+            }
+        };
+
+        let mut game = Game::new(board);
+        for &text in tokens.iter().skip(moves_at + 1) {
+            if let Err(error) = game.play_uci(text) {
+                self.send(&format!("info string {}", error));
+                break;
+            }
+        }
+
+        self.engine().set_board(game.board().clone());
+    }
+
+    fn go(&mut self, tokens: &[&str]) {
+        let params = GoParams::parse(tokens);
+        let output = Arc::clone(&self.output);
+        let signals = Arc::clone(&self.signals);
+
+        let engine = self.engine();
+        let board = engine.board().clone();
+
+        let search_actions = params
+            .search_moves
+            .iter()
+            .filter_map(|text| board.parse_uci_move(text).ok())
+            .collect();
+
+        engine.set_max_depth(max_depth(&params));
+        engine.set_time_limit(allocate_time(&params, board.side_to_move));
+        engine.set_node_limit(params.nodes);
+        engine.set_search_actions(search_actions);
+
+        let info_output = Arc::clone(&output);
+        engine.set_info_callback(Some(Box::new(move |info| {
+            send(&info_output, &format_info(info));
+        })));
+
+        let mut engine = self
+            .engine
+            .take()
+            .expect("engine is owned by the front end");
+        signals.stop.store(false, Ordering::Relaxed);
+        signals.ponder.store(params.ponder, Ordering::Relaxed);
+
+        self.worker = Some(thread::spawn(move || {
+            let result = engine.search();
+
+            // bestmove has to wait until the GUI ends an infinite or ponder search
+            while (params.infinite || signals.ponder.load(Ordering::Relaxed))
+                && !signals.stop.load(Ordering::Relaxed)
+            {
+                thread::sleep(Duration::from_millis(1));
+            }
+
+            let best_move = result
+                .best_action
+                .as_ref()
+                .map_or("0000".to_string(), Action::to_uci);
+            send(&output, &format!("bestmove {}", best_move));
+            engine
+        }));
+    }
+
+    fn set_option(&mut self, tokens: &[&str]) {
+        let value_at = tokens
+            .iter()
+            .position(|&token| token == "value")
+            .unwrap_or(tokens.len());
+        let name = tokens[..value_at]
+            .iter()
+            .skip_while(|&&token| token == "name")
+            .copied()
+            .collect::<Vec<&str>>()
+            .join(" ");
+        let value = tokens.get(value_at + 1..).unwrap_or_default().join(" ");
+
+        match name.to_lowercase().as_str() {
+            // Pondering is driven entirely by the GUI
+            "ponder" => {}
+            "nullmoveverification" => self
+                .engine()
+                .set_null_move_verification(value.eq_ignore_ascii_case("true")),
+            _ => self.send(&format!("info string unknown option {}", name)),
+        }
+    }
+
+    fn send(&self, line: &str) {
+        send(&self.output, line);
+    }
+}
+
+#[derive(Debug, Default)]
+struct GoParams {
+    search_moves: Vec<String>,
+    ponder: bool,
+    white_time: Option<Duration>,
+    black_time: Option<Duration>,
+    white_increment: Duration,
+    black_increment: Duration,
+    moves_to_go: Option<u32>,
+    depth: Option<i32>,
+    nodes: Option<i64>,
+    mate: Option<i32>,
+    move_time: Option<Duration>,
+    infinite: bool,
+}
+
+impl GoParams {
+    fn parse(tokens: &[&str]) -> Self {
+        let mut params = GoParams::default();
+        let mut tokens = tokens.iter().peekable();
+
+        while let Some(&keyword) = tokens.next() {
+            if keyword == "searchmoves" {
+                while let Some(&&text) = tokens.peek() {
+                    if GO_KEYWORDS.contains(&text) {
+                        break;
+                    }
+                    params.search_moves.push(text.to_string());
+                    tokens.next();
+                }
+                continue;
+            }
+
+            if keyword == "ponder" || keyword == "infinite" {
+                params.ponder |= keyword == "ponder";
+                params.infinite |= keyword == "infinite";
+                continue;
+            }
+
+            let Some(value) = tokens.next() else {
+                break;
+            };
+            // Some GUIs send negative times once the clock has run out
+            let millis = value
+                .parse::<i64>()
+                .ok()
+                .map(|millis| Duration::from_millis(millis.max(0) as u64));
+
+            match keyword {
+                "wtime" => params.white_time = millis,
+                "btime" => params.black_time = millis,
+                "winc" => params.white_increment = millis.unwrap_or_default(),
+                "binc" => params.black_increment = millis.unwrap_or_default(),
+                "movestogo" => params.moves_to_go = value.parse().ok(),
+                "depth" => params.depth = value.parse().ok(),
+                "nodes" => params.nodes = value.parse().ok(),
+                "mate" => params.mate = value.parse().ok(),
+                "movetime" => params.move_time = millis,
+                _ => {}
+            }
+        }
+
+        params
+    }
+}
+
+// A mate in n moves is found within 2n - 1 plies
+fn max_depth(params: &GoParams) -> i32 {
+    params
+        .depth
+        .or(params
+            .mate
+            .map(|moves| moves.saturating_mul(2).saturating_sub(1)))
+        .unwrap_or(MAX_PLY as i32 - 1)
+        .clamp(1, MAX_PLY as i32 - 1)
+}
+
+fn allocate_time(params: &GoParams, color: Color) -> Option<Duration> {
+    if params.infinite {
+        return None;
+    }
+
+    if let Some(move_time) = params.move_time {
+        return Some(move_time.saturating_sub(MOVE_OVERHEAD));
+    }
+
+    let (time, increment) = match color {
+        Color::White => (params.white_time?, params.white_increment),
+        Color::Black => (params.black_time?, params.black_increment),
+    };
+
+    Some(allocate_clock_time(time, increment, params.moves_to_go))
+}
+
+fn format_info(info: &SearchInfo) -> String {
+    match info {
+        SearchInfo::Iteration(result) => format_result(result),
+        SearchInfo::FailHigh {
+            depth,
+            score,
+            nodes,
+        } => format!(
+            "info depth {} score {} lowerbound nodes {}",
+            depth,
+            format_score(*score),
+            nodes
+        ),
+        SearchInfo::FailLow {
+            depth,
+            score,
+            nodes,
+        } => format!(
+            "info depth {} score {} upperbound nodes {}",
+            depth,
+            format_score(*score),
+            nodes
+        ),
+    }
+}
+
+fn format_result(result: &SearchResult) -> String {
+    let millis = result.time.as_millis().max(1);
+    let mut line = format!(
+        "info depth {} score {} nodes {} nps {} time {}",
+        result.depth,
+        format_score(result.score),
+        result.nodes,
+        result.nodes as u128 * 1000 / millis,
+        result.time.as_millis()
+    );
+
+    if let Some(action) = &result.best_action {
+        line.push_str(&format!(" pv {}", action.to_uci()));
+    }
+
+    line
+}
+
+fn format_score(score: i32) -> String {
+    if score.abs() < MATE_BOUND {
+        return format!("cp {}", score);
+    }
+
+    let moves = (MATE_SCORE - score.abs() + 1) / 2;
+    match score > 0 {
+        true => format!("mate {}", moves),
+        _ => format!("mate -{}", moves),
+    }
+}
+
+pub(crate) fn send(output: &SharedOutput, line: &str) {
+    let mut output = output.lock().expect("output lock poisoned");
+    // Nothing useful can be done if the GUI has gone away
+    let _ = writeln!(output, "{}", line);
+    let _ = output.flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::Cursor,
+        sync::{Arc, Mutex},
+        thread,
+        time::Duration,
+    };
+
+    use super::{allocate_time, format_score, max_depth, run, GoParams, SharedOutput, Uci};
+    use crate::{engine::MAX_PLY, evaluation::MATE_SCORE, piece::Color};
+
+    fn capture() -> (Arc<Mutex<Vec<u8>>>, SharedOutput) {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let output: SharedOutput = buffer.clone();
+        (buffer, output)
+    }
+
+    fn captured(buffer: &Arc<Mutex<Vec<u8>>>) -> String {
+        String::from_utf8(buffer.lock().unwrap().clone()).unwrap()
+    }
+
+    fn run_search(commands: &[&str]) -> String {
+        let (buffer, output) = capture();
+        let mut uci = Uci::new(output);
+
+        for command in commands {
+            assert!(uci.handle_command(command));
+        }
+        uci.wait_for_search();
+
+        captured(&buffer)
+    }
+
+    #[test]
+    fn handshake_and_ready() {
+        let (buffer, output) = capture();
+
+        run(Cursor::new("uci\nisready\nquit\n"), output);
+        let output = captured(&buffer);
+
+        assert!(output.contains("id name rust-krabs"));
+        assert!(output.contains("uciok"));
+        assert!(output.ends_with("readyok\n"));
+    }
+
+    #[test]
+    fn go_depth_reports_info_and_bestmove() {
+        let output = run_search(&["position startpos moves e2e4 e7e5", "go depth 2"]);
+
+        assert!(output.contains("info depth 1 score cp"));
+        assert!(output.contains("info depth 2 score cp"));
+        assert!(output.lines().last().unwrap().starts_with("bestmove "));
+    }
+
+    #[test]
+    fn go_infinite_waits_for_stop() {
+        let (buffer, output) = capture();
+        let mut uci = Uci::new(output);
+
+        uci.handle_command("position fen 4k3/8/8/8/8/8/8/R3K3 w Q - 0 1");
+        uci.handle_command("go infinite depth 1");
+        thread::sleep(Duration::from_millis(200));
+        assert!(!captured(&buffer).contains("bestmove"));
+
+        uci.handle_command("stop");
+        let output = captured(&buffer);
+
+        assert!(output.contains("info depth 1"));
+        assert!(output.contains("bestmove "));
+    }
+
+    #[test]
+    fn position_applies_castling_moves() {
+        let output = run_search(&[
+            "position fen r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1 moves e1g1 e8c8",
+            "go depth 1",
+        ]);
+
+        assert!(!output.contains("illegal move"));
+        assert!(output.contains("bestmove "));
+    }
+
+    #[test]
+    fn reports_illegal_moves() {
+        let output = run_search(&["position startpos moves e2e5"]);
+
+        assert!(output.contains("info string illegal move e2e5"));
+    }
+
+    #[test]
+    fn invalid_fens_keep_the_previous_position() {
+        let (buffer, output) = capture();
+        let mut uci = Uci::new(output);
+
+        uci.handle_command("position startpos moves e2e4");
+        let fen = uci.engine().board().to_fen();
+        uci.handle_command("position fen");
+        uci.handle_command("position fen kkkkkkkkk/8/8/8/8/8/8/K7 w - - 0 1");
+
+        let output = captured(&buffer);
+        assert!(output.contains("info string invalid fen missing piece placement"));
+        assert!(output.contains("info string invalid fen invalid piece placement kkkkkkkkk"));
+        assert_eq!(uci.engine().board().to_fen(), fen);
+    }
+
+    #[test]
+    fn parses_go_parameters() {
+        let params = GoParams::parse(&[
+            "searchmoves",
+            "e2e4",
+            "d2d4",
+            "wtime",
+            "60000",
+            "btime",
+            "-5",
+            "winc",
+            "1000",
+            "movestogo",
+            "20",
+            "ponder",
+        ]);
+
+        assert_eq!(params.search_moves, vec!["e2e4", "d2d4"]);
+        assert_eq!(params.white_time, Some(Duration::from_secs(60)));
+        assert_eq!(params.black_time, Some(Duration::ZERO));
+        assert_eq!(params.white_increment, Duration::from_secs(1));
+        assert_eq!(params.moves_to_go, Some(20));
+        assert!(params.ponder);
+        assert!(!params.infinite);
+    }
+
+    #[test]
+    fn mate_searches_are_limited_to_the_maximum_depth() {
+        assert_eq!(max_depth(&GoParams::parse(&["mate", "2"])), 3);
+        assert_eq!(
+            max_depth(&GoParams::parse(&["mate", "2000000000"])),
+            MAX_PLY as i32 - 1
+        );
+        assert_eq!(max_depth(&GoParams::parse(&["depth", "-3"])), 1);
+
+        let output = run_search(&[
+            "position fen 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1",
+            "go mate 2000000000 nodes 5000",
+        ]);
+        assert!(output.contains("bestmove a1a8"));
+    }
+
+    #[test]
+    fn allocates_time_from_the_clock() {
+        let params = GoParams::parse(&["wtime", "60000", "btime", "30000", "winc", "1000"]);
+
+        assert_eq!(
+            allocate_time(&params, Color::White),
+            Some(Duration::from_millis(2750))
+        );
+        assert_eq!(
+            allocate_time(&params, Color::Black),
+            Some(Duration::from_millis(1000))
+        );
+        assert_eq!(
+            allocate_time(&GoParams::parse(&["movetime", "500"]), Color::White),
+            Some(Duration::from_millis(450))
+        );
+        assert_eq!(
+            allocate_time(&GoParams::parse(&["infinite"]), Color::White),
+            None
+        );
+    }
+
+    #[test]
+    fn formats_mate_scores() {
+        assert_eq!(format_score(35), "cp 35");
+        assert_eq!(format_score(MATE_SCORE - 1), "mate 1");
+        assert_eq!(format_score(MATE_SCORE - 3), "mate 2");
+        assert_eq!(format_score(-MATE_SCORE + 2), "mate -1");
+    }
+}
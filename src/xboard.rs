@@ -0,0 +1,580 @@
+use std::{
+    io::BufRead,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::{
+    action::{Action, MoveParseError},
+    board::{Board, DrawReason, DEFAULT_FEN},
+    engine::{
+        allocate_time, Engine, SearchInfo, SearchResult, SearchSignals, MAX_PLY, MOVE_OVERHEAD,
+    },
+    evaluation::{MATE_BOUND, MATE_SCORE},
+    game::{Clock, Game, GameResult, Termination},
+    piece::Color,
+    uci::{send, SharedOutput},
+};
+
+// Spent on each move under `level` until the GUI reports the clock with `time`
+const DEFAULT_MOVE_TIME: Duration = Duration::from_secs(1);
+
+pub fn run(input: impl BufRead, output: SharedOutput) {
+    let mut xboard = XBoard::new(output);
+
+    for line in input.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+
+        if !xboard.handle_command(&line) {
+            break;
+        }
+    }
+
+    xboard.abandon_search();
+}
+
+#[derive(Debug, Clone, Copy)]
+enum TimeControl {
+    // `level`: a number of moves (0 for the whole game) per session, plus increment
+    Level {
+        moves_per_session: u32,
+        increment: Duration,
+    },
+    // `st`: a fixed time for every move
+    PerMove(Duration),
+}
+
+pub struct XBoard {
+    // Lent to the worker thread while a search is running
+    engine: Option<Engine>,
+    worker: Option<JoinHandle<Engine>>,
+    signals: Arc<SearchSignals>,
+    // Set when a running search should end without playing its move
+    abandoned: Arc<AtomicBool>,
+    // The moves played since the last new or setboard, so they can be taken back
+    game: Arc<Mutex<Game>>,
+    output: SharedOutput,
+    // None in force mode, when the engine only records moves
+    engine_color: Option<Color>,
+    time_control: Option<TimeControl>,
+    max_depth: Option<i32>,
+    post: bool,
+}
+
+impl XBoard {
+    pub fn new(output: SharedOutput) -> Self {
+        let engine = Engine::new(DEFAULT_FEN);
+        let signals = engine.signals();
+
+        XBoard {
+            engine: Some(engine),
+            worker: None,
+            signals,
+            abandoned: Arc::new(AtomicBool::new(false)),
+            game: Arc::new(Mutex::new(Game::new(Board::new(DEFAULT_FEN)))),
+            output,
+            engine_color: Some(Color::Black),
+            time_control: None,
+            max_depth: None,
+            post: false,
+        }
+    }
+
+    // Returns false once the GUI has asked us to quit
+    pub fn handle_command(&mut self, line: &str) -> bool {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+
+        match tokens.first() {
+            Some(&"protover") => self.send_features(),
+            Some(&"new") => {
+                self.abandon_search();
+                self.set_position(Board::new(DEFAULT_FEN));
+                self.engine_color = Some(Color::Black);
+                self.max_depth = None;
+            }
+            Some(&"force") => {
+                self.abandon_search();
+                self.engine_color = None;
+            }
+            Some(&"go") => {
+                self.wait_for_search();
+                self.engine_color = Some(self.side_to_move());
+                self.think();
+            }
+            Some(&"usermove") => self.user_move(tokens.get(1).copied().unwrap_or_default()),
+            Some(&"level") => self.set_level(&tokens[1..]),
+            Some(&"st") => {
+                let seconds = tokens.get(1).and_then(|value| value.parse().ok());
+                self.time_control =
+                    seconds.map(|seconds| TimeControl::PerMove(Duration::from_secs(seconds)));
+            }
+            Some(&"sd") => self.max_depth = tokens.get(1).and_then(|value| value.parse().ok()),
+            // time is always the engine's clock and otim its opponent's
+            Some(&"time") => {
+                let color = self.engine_side();
+                self.set_clock(color, tokens.get(1).copied());
+            }
+            Some(&"otim") => {
+                let color = self.engine_side().opposite();
+                self.set_clock(color, tokens.get(1).copied());
+            }
+            Some(&"undo") => self.undo(1),
+            Some(&"remove") => self.undo(2),
+            Some(&"result") => {
+                self.abandon_search();
+                self.engine_color = None;
+            }
+            Some(&"setboard") => {
+                self.abandon_search();
+                match Board::from_fen(&tokens[1..].join(" ")) {
+                    Ok(board) => self.set_position(board),
+                    Err(_) => self.send("tellusererror Illegal position"),
+                }
+            }
+            Some(&"post") => self.post = true,
+            Some(&"nopost") => self.post = false,
+            Some(&"ping") => self.send(&format!("pong {}", tokens.get(1).unwrap_or(&""))),
+            // Move now
+            Some(&"?") => self.wait_for_search_with_stop(),
+            Some(&"quit") => return false,
+            Some(&"xboard") | Some(&"accepted") | Some(&"rejected") | Some(&"random")
+            | Some(&"hard") | Some(&"easy") | Some(&"computer") | Some(&"name")
+            | Some(&"rating") | Some(&"ics") | None => {}
+            // Without the usermove feature moves arrive bare, so anything shaped
+            // like a move is answered as one, legal or not
+            Some(&text)
+                if !matches!(
+                    self.current_board().parse_uci_move(text),
+                    Err(MoveParseError::InvalidFormat(_))
+                ) =>
+            {
+                self.user_move(text)
+            }
+            Some(command) => self.send(&format!("Error (unknown command): {}", command)),
+        }
+
+        true
+    }
+
+    // Stops any running search without letting it play its move
+    pub fn abandon_search(&mut self) {
+        self.abandoned.store(true, Ordering::Relaxed);
+        self.wait_for_search_with_stop();
+    }
+
+    fn wait_for_search_with_stop(&mut self) {
+        self.signals.stop.store(true, Ordering::Relaxed);
+        self.wait_for_search();
+    }
+
+    pub fn wait_for_search(&mut self) {
+        if let Some(worker) = self.worker.take() {
+            self.engine = Some(worker.join().expect("search thread panicked"));
+        }
+    }
+
+    fn send_features(&self) {
+        self.send(&format!(
+            "feature myname=\"{} {}\" setboard=1 usermove=1 time=1 ping=1 draw=0 sigint=0 sigterm=0 colors=0 analyze=0 done=1",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION")
+        ));
+    }
+
+    fn user_move(&mut self, text: &str) {
+        self.wait_for_search_with_stop();
+
+        let board = self.current_board();
+        let Ok(action) = board.parse_uci_move(text) else {
+            self.send(&format!("Illegal move: {}", text));
+            return;
+        };
+
+        let result = {
+            let mut game = self.game.lock().expect("game lock poisoned");
+            game.play(action);
+            result_line(&game)
+        };
+
+        if let Some(result) = result {
+            self.send(&result);
+            return;
+        }
+
+        if self.engine_color == Some(self.side_to_move()) {
+            self.think();
+        }
+    }
+
+    fn undo(&mut self, count: usize) {
+        self.abandon_search();
+        self.game.lock().expect("game lock poisoned").undo(count);
+    }
+
+    // level MPS BASE INC, where BASE is minutes or minutes:seconds
+    fn set_level(&mut self, tokens: &[&str]) {
+        let moves_per_session = tokens.first().and_then(|value| value.parse().ok());
+        let increment = tokens.get(2).and_then(|value| value.parse::<f64>().ok());
+
+        self.time_control = match (moves_per_session, increment) {
+            (Some(moves_per_session), Some(increment)) => Some(TimeControl::Level {
+                moves_per_session,
+                increment: Duration::from_secs_f64(increment.max(0.0)),
+            }),
+            _ => None,
+        };
+    }
+
+    fn think(&mut self) {
+        let (board, moves_played, clock) = {
+            let game = self.game.lock().expect("game lock poisoned");
+            if game.is_over() {
+                return;
+            }
+            let board = game.board().clone();
+            let clock = game
+                .clock()
+                .map(|clock| clock.remaining(board.side_to_move));
+            (board, game.moves().len() / 2, clock)
+        };
+        let time_limit = self.time_limit(moves_played as u32, clock);
+        let max_depth = self.max_depth.unwrap_or(MAX_PLY as i32 - 1);
+        let post = self.post;
+        let output = Arc::clone(&self.output);
+        let game = Arc::clone(&self.game);
+        let abandoned = Arc::clone(&self.abandoned);
+
+        let mut engine = self
+            .engine
+            .take()
+            .expect("engine is owned by the front end");
+        engine.set_board(board);
+        engine.set_max_depth(max_depth);
+        engine.set_time_limit(time_limit);
+        engine.set_node_limit(None);
+        engine.set_search_actions(Vec::new());
+
+        let info_output = Arc::clone(&output);
+        engine.set_info_callback(Some(Box::new(move |info| {
+            if let (true, SearchInfo::Iteration(result)) = (post, info) {
+                send(&info_output, &format_thinking(result));
+            }
+        })));
+
+        self.signals.stop.store(false, Ordering::Relaxed);
+        self.signals.ponder.store(false, Ordering::Relaxed);
+        self.abandoned.store(false, Ordering::Relaxed);
+
+        self.worker = Some(thread::spawn(move || {
+            let result = engine.search();
+            if abandoned.load(Ordering::Relaxed) {
+                return engine;
+            }
+
+            let Some(action) = result.best_action else {
+                return engine;
+            };
+
+            // The move is recorded under the same lock it is announced with, so
+            // the next command always sees it
+            let mut game = game.lock().expect("game lock poisoned");
+            send(&output, &format!("move {}", action.to_uci()));
+            game.play(action);
+
+            if let Some(result) = result_line(&game) {
+                send(&output, &result);
+            }
+
+            engine
+        }));
+    }
+
+    fn time_limit(&self, moves_played: u32, clock: Option<Duration>) -> Option<Duration> {
+        match self.time_control? {
+            TimeControl::PerMove(time) => Some(time.saturating_sub(MOVE_OVERHEAD)),
+            TimeControl::Level {
+                moves_per_session,
+                increment,
+            } => {
+                let Some(clock) = clock else {
+                    return Some(DEFAULT_MOVE_TIME);
+                };
+                let moves_to_go = (moves_per_session > 0)
+                    .then(|| moves_per_session - moves_played % moves_per_session);
+
+                Some(allocate_time(clock, increment, moves_to_go))
+            }
+        }
+    }
+
+    // Kept on the game, where the side the engine isn't playing is also recorded
+    fn set_clock(&mut self, color: Color, value: Option<&str>) {
+        let Some(remaining) = value.and_then(parse_centiseconds) else {
+            return;
+        };
+        let increment = match self.time_control {
+            Some(TimeControl::Level { increment, .. }) => increment,
+            _ => Duration::ZERO,
+        };
+
+        let mut game = self.game.lock().expect("game lock poisoned");
+        let mut clock = game
+            .clock()
+            .copied()
+            .unwrap_or(Clock::new(remaining, increment));
+        clock.remaining[color.index()] = remaining;
+        game.set_clock(Some(clock));
+    }
+
+    fn set_position(&mut self, board: Board) {
+        *self.game.lock().expect("game lock poisoned") = Game::new(board);
+    }
+
+    fn current_board(&self) -> Board {
+        self.game
+            .lock()
+            .expect("game lock poisoned")
+            .board()
+            .clone()
+    }
+
+    // In force mode the engine plays whichever side `go` finds to move
+    fn engine_side(&self) -> Color {
+        self.engine_color.unwrap_or_else(|| self.side_to_move())
+    }
+
+    fn side_to_move(&self) -> Color {
+        self.current_board().side_to_move
+    }
+
+    fn send(&self, line: &str) {
+        send(&self.output, line);
+    }
+}
+
+fn parse_centiseconds(value: &str) -> Option<Duration> {
+    let centiseconds = value.parse::<i64>().ok()?;
+    Some(Duration::from_millis(centiseconds.max(0) as u64 * 10))
+}
+
+// Thinking output is "ply score time nodes pv", with time in centiseconds and
+// mates reported as 100000 + moves
+fn format_thinking(result: &SearchResult) -> String {
+    let score = match result.score.abs() >= MATE_BOUND {
+        true => {
+            let moves = (MATE_SCORE - result.score.abs() + 1) / 2;
+            result.score.signum() * (100_000 + moves)
+        }
+        _ => result.score,
+    };
+    let pv = result
+        .best_action
+        .as_ref()
+        .map_or(String::new(), Action::to_uci);
+
+    format!(
+        "{} {} {} {} {}",
+        result.depth,
+        score,
+        result.time.as_millis() / 10,
+        result.nodes,
+        pv
+    )
+}
+
+fn result_line(game: &Game) -> Option<String> {
+    let line = match (game.termination()?, game.result()) {
+        (Termination::Checkmate, GameResult::WhiteWins) => "1-0 {White mates}",
+        (Termination::Checkmate, _) => "0-1 {Black mates}",
+        (Termination::Stalemate, _) => "1/2-1/2 {Stalemate}",
+        (Termination::Draw(DrawReason::FiftyMoveRule), _) => "1/2-1/2 {Fifty move rule}",
+        (Termination::Draw(DrawReason::ThreefoldRepetition), _) => "1/2-1/2 {Draw by repetition}",
+        (Termination::Draw(DrawReason::InsufficientMaterial), _) => {
+            "1/2-1/2 {Insufficient material}"
+        }
+        (_, result) => return Some(result.as_pgn().to_string()),
+    };
+
+    Some(line.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::Cursor,
+        sync::{Arc, Mutex},
+        thread,
+        time::Duration,
+    };
+
+    use super::{parse_centiseconds, run, SharedOutput, XBoard, DEFAULT_MOVE_TIME};
+    use crate::{engine::allocate_time, piece::Color};
+
+    fn capture() -> (Arc<Mutex<Vec<u8>>>, SharedOutput) {
+        let buffer = Arc::new(Mutex::new(Vec::new()));
+        let output: SharedOutput = buffer.clone();
+        (buffer, output)
+    }
+
+    fn captured(buffer: &Arc<Mutex<Vec<u8>>>) -> String {
+        String::from_utf8(buffer.lock().unwrap().clone()).unwrap()
+    }
+
+    fn run_game(commands: &[&str]) -> String {
+        let (buffer, output) = capture();
+        let mut xboard = XBoard::new(output);
+
+        for command in commands {
+            assert!(xboard.handle_command(command));
+            xboard.wait_for_search();
+        }
+
+        captured(&buffer)
+    }
+
+    #[test]
+    fn announces_features() {
+        let (buffer, output) = capture();
+
+        run(Cursor::new("xboard\nprotover 2\nping 7\nquit\n"), output);
+        let output = captured(&buffer);
+
+        assert!(output.contains("feature myname=\"rust-krabs"));
+        assert!(output.contains("usermove=1"));
+        assert!(output.ends_with("pong 7\n"));
+    }
+
+    #[test]
+    fn replies_to_user_moves_as_black() {
+        let output = run_game(&["new", "sd 2", "usermove e2e4"]);
+
+        assert!(output.lines().any(|line| line.starts_with("move ")));
+    }
+
+    #[test]
+    fn force_mode_only_records_moves() {
+        let output = run_game(&["new", "force", "usermove e2e4", "usermove e7e5"]);
+
+        assert!(!output.contains("move "));
+    }
+
+    #[test]
+    fn go_plays_the_side_to_move_with_thinking_output() {
+        let output = run_game(&["new", "force", "post", "sd 2", "go"]);
+
+        assert!(output.lines().any(|line| line.starts_with("2 ")));
+        assert!(output.lines().last().unwrap().starts_with("move "));
+    }
+
+    #[test]
+    fn rejects_illegal_moves() {
+        let output = run_game(&["new", "force", "usermove e2e5", "e7e4", "e2e4x"]);
+
+        assert!(output.contains("Illegal move: e2e5"));
+        assert!(output.contains("Illegal move: e7e4"));
+        assert!(output.contains("Error (unknown command): e2e4x"));
+    }
+
+    #[test]
+    fn user_moves_stop_the_search() {
+        let (buffer, output) = capture();
+        let mut xboard = XBoard::new(output);
+
+        for command in ["new", "force", "go"] {
+            xboard.handle_command(command);
+        }
+        thread::sleep(Duration::from_millis(100));
+        xboard.handle_command("usermove e7e5");
+        xboard.abandon_search();
+
+        assert!(captured(&buffer).contains("move "));
+        assert_eq!(xboard.game.lock().unwrap().moves().len(), 2);
+    }
+
+    #[test]
+    fn level_budgets_a_move_without_the_clock() {
+        let (_, output) = capture();
+        let mut xboard = XBoard::new(output);
+
+        xboard.handle_command("level 40 5 0");
+        assert_eq!(xboard.time_limit(0, None), Some(DEFAULT_MOVE_TIME));
+
+        xboard.handle_command("time 6000");
+        xboard.handle_command("otim 3000");
+        let clock = *xboard.game.lock().unwrap().clock().unwrap();
+        assert_eq!(clock.remaining(Color::Black), Duration::from_secs(60));
+        assert_eq!(clock.remaining(Color::White), Duration::from_secs(30));
+        assert_eq!(
+            xboard.time_limit(0, Some(clock.remaining(Color::Black))),
+            Some(allocate_time(
+                Duration::from_secs(60),
+                Duration::ZERO,
+                Some(40)
+            ))
+        );
+    }
+
+    #[test]
+    fn undo_and_remove_take_moves_back() {
+        let (_, output) = capture();
+        let mut xboard = XBoard::new(output);
+
+        for command in ["new", "force", "e2e4", "e7e5", "g1f3", "remove"] {
+            xboard.handle_command(command);
+        }
+        assert_eq!(xboard.game.lock().unwrap().moves().len(), 1);
+
+        xboard.handle_command("undo");
+        assert_eq!(xboard.current_board().side_to_move, Color::White);
+        assert!(xboard.game.lock().unwrap().moves().is_empty());
+    }
+
+    #[test]
+    fn reports_mate_after_the_engine_moves() {
+        let output = run_game(&[
+            "force",
+            "setboard 6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1",
+            "sd 3",
+            "go",
+        ]);
+
+        assert!(output.contains("move a1a8"));
+        assert!(output.contains("1-0 {White mates}"));
+    }
+
+    #[test]
+    fn setboard_rejects_illegal_positions() {
+        let (buffer, output) = capture();
+        let mut xboard = XBoard::new(output);
+
+        for command in [
+            "new",
+            "force",
+            "e2e4",
+            "setboard",
+            "setboard kkkkkkkkk/8/8/8/8/8/8/K7 w - - 0 1",
+        ] {
+            xboard.handle_command(command);
+        }
+
+        assert_eq!(
+            captured(&buffer)
+                .matches("tellusererror Illegal position")
+                .count(),
+            2
+        );
+        assert_eq!(xboard.game.lock().unwrap().moves().len(), 1);
+    }
+
+    #[test]
+    fn parses_clock_in_centiseconds() {
+        assert_eq!(parse_centiseconds("6000").unwrap().as_secs(), 60);
+        assert_eq!(parse_centiseconds("-20").unwrap().as_millis(), 0);
+    }
+}